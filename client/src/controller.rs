@@ -14,31 +14,34 @@ pub struct Controller {
     pub tx: mpsc::Sender<ControllerMessage>,
     pub socket: TcpStream,
     pub ui: Ui,
+    /// Engine strength requested when connecting, for games against the computer.
+    pub difficulty: Option<Difficulty>,
 }
 
 pub enum ControllerMessage {
     /// Used to send updated data to server, if move was performed.
     MovePerformed(Move),
+    /// Used to ask the server for a hint.
+    HintRequested,
     /// Used to send updated gamestate from server to ui.
-    UpdateState(GameState),
+    UpdateState(Box<GameState>),
     /// Used to notify ui, that server is down.
     ServerIsDown,
 }
 
 impl Controller {
     /// Create a new controller
-    pub fn new(server_addr: &str) -> Result<Controller, String> {
+    pub fn new(server_addr: &str, difficulty: Option<Difficulty>) -> Result<Controller, String> {
         let (tx, rx) = mpsc::channel::<ControllerMessage>();
-        let stream = TcpStream::connect(server_addr);
-        if stream.is_err() {
-            Err("Can't connect server.".to_string())
-        } else {
-            Ok(Controller {
-                rx: rx,
+        match TcpStream::connect(server_addr) {
+            Err(_) => Err("Can't connect server.".to_string()),
+            Ok(socket) => Ok(Controller {
+                rx,
                 tx: tx.clone(),
-                socket: stream.unwrap(),
-                ui: Ui::new(tx.clone()),
-            })
+                socket,
+                ui: Ui::new(tx),
+                difficulty,
+            }),
         }
     }
     /// Write json data to server.
@@ -55,13 +58,20 @@ impl Controller {
 
     /// Run the controller
     pub fn run(&mut self) {
+        let name = std::env::var("USER").unwrap_or_else(|_| String::from("horse-client"));
+        self.write_json_data(&Request::Hello(Hello {
+            name,
+            bot: false,
+            difficulty: self.difficulty.clone(),
+        }));
+
         let mut buffer = String::new();
         let mut reader = BufReader::new(&self.socket);
         reader.read_line(&mut buffer).unwrap();
         let current_player: Option<Player> = serde_json::from_str(&buffer).unwrap();
         self.ui
             .ui_tx
-            .send(UiMessage::UpdateProfile(current_player)).unwrap();
+            .send(UiMessage::UpdateProfile(current_player.clone())).unwrap();
         buffer.clear();
         let controller_tx = self.tx.clone();
         let socket = self.socket.try_clone();
@@ -81,7 +91,7 @@ impl Controller {
                     } else {
                         let state: GameState = serde_json::from_str(&buffer).unwrap();
                         controller_tx
-                            .send(ControllerMessage::UpdateState(state.clone()))
+                            .send(ControllerMessage::UpdateState(Box::new(state.clone())))
                             .unwrap();
                         if state.finished {
                             break;
@@ -102,6 +112,11 @@ impl Controller {
                         self.write_json_data(&new_state);
                         // let server_state = self.get_new_state();
                     }
+                    ControllerMessage::HintRequested => {
+                        if let Some(player) = current_player.clone() {
+                            self.write_json_data(&Request::Hint(player));
+                        }
+                    }
                     ControllerMessage::UpdateState(server_state) => {
                         self.ui
                             .ui_tx
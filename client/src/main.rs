@@ -4,11 +4,39 @@ mod types;
 
 mod controller;
 use controller::Controller;
+use types::Difficulty;
 
 mod ui;
 
+const USAGE: &str = "Usage: horse-client [--difficulty LEVEL]";
+
+/// Parses command line arguments (without program name):
+/// engine difficulty to request, if any.
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Option<Difficulty>, String> {
+    let mut difficulty = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--difficulty" => {
+                let value = args.next().ok_or("--difficulty requires a value")?;
+                difficulty = Some(value.parse()?);
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    Ok(difficulty)
+}
+
 fn main() {
-    let controller = Controller::new("127.0.0.1:31337");
+    let difficulty = match parse_args(std::env::args().skip(1)) {
+        Ok(difficulty) => difficulty,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let controller = Controller::new("127.0.0.1:31337", difficulty);
     match controller {
         Ok(mut controller) => controller.run(),
         Err(e) => println!("Error: {}", e),
@@ -1,6 +1,8 @@
 use crate::controller::ControllerMessage;
+use crate::types::column_letter;
 use crate::types::Move;
 use crate::types::Position;
+use crate::types::Topology;
 use cursive::direction::Direction;
 use cursive::event::{Event, EventResult, MouseEvent};
 use cursive::theme::{BaseColor, Color, ColorStyle};
@@ -42,11 +44,21 @@ impl BoardView {
         }
     }
 
+    /// Width of the column with row numbers.
+    fn label_width(&self) -> usize {
+        (self.gamestate.height.saturating_sub(1)).to_string().len()
+    }
+
     /// Map coordinates from View to chessboard coordinate
     fn get_cell(&mut self, position: Vec2, offset: Vec2) -> Option<Vec2> {
-        if let Some(pos) = position.checked_sub(offset) {
+        let label = Vec2::from((self.label_width(), 1));
+        if let Some(pos) = position.checked_sub(offset + label) {
             let pos = Vec2::from((pos.x / 4, pos.y / 4));
-            if pos.fits_in((7, 7)) {
+            let last = Vec2::from((
+                self.gamestate.width as usize - 1,
+                self.gamestate.height as usize - 1,
+            ));
+            if pos.fits_in(last) {
                 Some(pos)
             } else {
                 None
@@ -58,79 +70,95 @@ impl BoardView {
     /// Add all available positions to move from focused into
     /// BoardView.available.
     fn update_available(&mut self) {
-        fn check_cell(focused: Vec2, x: i8, y: i8, available: &mut Vec<Vec2>) {
-            let x = focused.x as i8 + x;
-            let y = focused.y as i8 + y;
-            if x > 7 || x < 0 {
-                return;
-            }
-            if y > 7 || y < 0 {
-                return;
-            }
-            available.push(Vec2::from((x as usize, y as usize)));
-        }
+        let width = i32::from(self.gamestate.width);
+        let height = i32::from(self.gamestate.height);
         if let Some(cell) = self.focused {
-            check_cell(cell.clone(), 2, 1, &mut self.available);
-            check_cell(cell.clone(), 2, -1, &mut self.available);
-            check_cell(cell.clone(), -2, 1, &mut self.available);
-            check_cell(cell.clone(), -2, -1, &mut self.available);
-            check_cell(cell.clone(), 1, 2, &mut self.available);
-            check_cell(cell.clone(), -1, 2, &mut self.available);
-            check_cell(cell.clone(), 1, -2, &mut self.available);
-            check_cell(cell.clone(), -1, -2, &mut self.available);
+            let kind = match &self.gamestate.board[cell.y][cell.x] {
+                Piece::Leaper { kind, .. } => &self.gamestate.kinds[*kind as usize],
+                _ => return,
+            };
+            for (dx, dy) in kind.offsets() {
+                let mut x = cell.x as i32 + dx;
+                let mut y = cell.y as i32 + dy;
+                if self.gamestate.topology == Topology::Toroidal {
+                    x = x.rem_euclid(width);
+                    y = y.rem_euclid(height);
+                }
+                if !(0..width).contains(&x) {
+                    continue;
+                }
+                if !(0..height).contains(&y) {
+                    continue;
+                }
+                if self.gamestate.board[y as usize][x as usize] == Piece::Blocked {
+                    continue;
+                }
+                self.available.push(Vec2::from((x as usize, y as usize)));
+            }
         }
     }
 }
 
 impl cursive::view::View for BoardView {
     fn draw(&self, printer: &Printer) {
-        let letters = vec!["a", "b", "c", "d", "e", "f", "g", "h"];
+        let label_width = self.label_width();
         for ci in 1..5 {
             for cj in 1..5 {
                 for (i, row) in self.gamestate.board.iter().enumerate() {
                     let i_size = i * 4 + ci;
                     for (j, piece) in row.iter().enumerate() {
-                        let j_size = j * 4 + cj;
+                        let j_size = j * 4 + cj + label_width - 1;
                         let is_black = (i + j) % 2 == 0;
-                        let mut back_color = match is_black {
-                            true => Color::RgbLowRes(3, 3, 3),
-                            false => Color::Dark(BaseColor::White),
+                        let mut back_color = if is_black {
+                            Color::RgbLowRes(3, 3, 3)
+                        } else {
+                            Color::Dark(BaseColor::White)
                         };
                         let position = Vec2::from((j, i));
                         let available = self.available.iter().any(|el| el == &position);
                         if available {
                             back_color = Color::RgbLowRes(1, 3, 1);
                         }
-                        let print_text = (ci == 3 && cj == 3)
-                            || (ci == 2 && cj == 2)
-                            || (ci == 2 && cj == 3)
-                            || (ci == 3 && cj == 2);
+                        let hinted = self.gamestate.hint.as_ref().is_some_and(|hint| {
+                            [&hint.from, &hint.to]
+                                .iter()
+                                .any(|square| Vec2::from((square.x, square.y)) == position)
+                        });
+                        if hinted {
+                            back_color = Color::RgbLowRes(4, 4, 1);
+                        }
+                        match piece {
+                            Piece::Blocked => back_color = Color::RgbLowRes(1, 1, 1),
+                            Piece::Hidden => back_color = Color::RgbLowRes(2, 2, 2),
+                            _ => (),
+                        }
+                        let print_text = (ci == 2 || ci == 3) && (cj == 2 || cj == 3);
                         let text = match piece {
-                            Piece::Empty => " ",
-                            Piece::Black => {
-                                if print_text {
-                                    "♞"
-                                } else {
-                                    " "
-                                }
-                            }
-                            Piece::White => {
-                                if print_text {
-                                    "♘"
-                                } else {
-                                    " "
-                                }
+                            Piece::Leaper { kind, owner } if print_text => {
+                                self.gamestate.kinds[*kind as usize].glyph(owner)
                             }
+                            Piece::Hidden if print_text => String::from("?"),
+                            _ => String::from(" "),
                         };
 
-                        printer.with_color(
-                            ColorStyle::new(Color::Dark(BaseColor::Black), back_color),
-                            |printer| printer.print((j_size, i_size), text),
-                        );
+                        let front_color = match piece {
+                            Piece::Leaper {
+                                owner: Player::Red, ..
+                            } => Color::Dark(BaseColor::Red),
+                            Piece::Leaper {
+                                owner: Player::Blue,
+                                ..
+                            } => Color::Dark(BaseColor::Blue),
+                            _ => Color::Dark(BaseColor::Black),
+                        };
+
+                        printer.with_color(ColorStyle::new(front_color, back_color), |printer| {
+                            printer.print((j_size, i_size), &text)
+                        });
 
-                        let mut letter = " ";
+                        let mut letter = String::from(" ");
                         if cj == 2 {
-                            letter = letters[j];
+                            letter = column_letter(j);
                         }
 
                         printer.with_color(
@@ -138,7 +166,7 @@ impl cursive::view::View for BoardView {
                                 Color::Dark(BaseColor::Black),
                                 Color::Dark(BaseColor::White),
                             ),
-                            |printer| printer.print((j_size, 0), letter),
+                            |printer| printer.print((j_size, 0), &letter),
                         );
                     }
                     if ci == 2 {
@@ -147,7 +175,9 @@ impl cursive::view::View for BoardView {
                                 Color::Dark(BaseColor::Black),
                                 Color::Dark(BaseColor::White),
                             ),
-                            |printer| printer.print((0, i_size), &i.to_string()),
+                            |printer| {
+                                printer.print((0, i_size), &format!("{:>1$}", i, label_width))
+                            },
                         );
                     } else {
                         printer.with_color(
@@ -155,7 +185,7 @@ impl cursive::view::View for BoardView {
                                 Color::Dark(BaseColor::Black),
                                 Color::Dark(BaseColor::White),
                             ),
-                            |printer| printer.print((0, i_size), " "),
+                            |printer| printer.print((0, i_size), &" ".repeat(label_width)),
                         );
                     }
                 }
@@ -164,7 +194,10 @@ impl cursive::view::View for BoardView {
     }
 
     fn required_size(&mut self, _: Vec2) -> Vec2 {
-        Vec2::from((33, 33))
+        Vec2::from((
+            self.gamestate.width as usize * 4 + self.label_width(),
+            self.gamestate.height as usize * 4 + 1,
+        ))
     }
 
     fn take_focus(&mut self, _: Direction) -> bool {
@@ -182,10 +215,9 @@ impl cursive::view::View for BoardView {
                 event: MouseEvent::Press(_btn),
             } => {
                 if let Some(cell) = self.get_cell(position, offset) {
-                    let test_color = match (&self.gamestate.board[cell.y][cell.x], &self.player) {
-                        (Piece::Black, Some(Player::Black)) => true,
-                        (Piece::White, Some(Player::White)) => true,
-                        _ => false,
+                    let test_color = match &self.player {
+                        Some(player) => *player == self.gamestate.board[cell.y][cell.x],
+                        None => false,
                     };
                     if test_color {
                         if self.focused != Some(cell) {
@@ -205,14 +237,13 @@ impl cursive::view::View for BoardView {
                 if let Some(pos) = self.get_cell(position, offset) {
                     if let Some(player) = &self.player {
                         if let Some(focused) = self.focused {
-                            let chess = match player.clone() {
-                                Player::White => Piece::White,
-                                Player::Black => Piece::Black,
-                            };
                             let available = self.available.iter().any(|el| el == &pos);
                             if available {
                                 self.focused = None;
-                                self.gamestate.board[focused.y][focused.x] = Piece::Empty;
+                                let chess = std::mem::replace(
+                                    &mut self.gamestate.board[focused.y][focused.x],
+                                    Piece::Empty,
+                                );
                                 self.gamestate.board[pos.y][pos.x] = chess;
                                 self.available.clear();
                                 let chess_move = Move {
@@ -1,38 +1,102 @@
 use crate::types::Board;
+use crate::types::BookMove;
+use crate::types::Handicap;
 use crate::types::History;
+use crate::types::Move;
+use crate::types::Piece;
+use crate::types::PieceKind;
 use crate::types::Player;
+use crate::types::Topology;
+use crate::types::Victory;
 use serde::Deserialize;
 use serde::Serialize;
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub struct GameState {
+    pub variant: String,
+    pub width: u8,
+    pub height: u8,
+    pub topology: Topology,
+    pub kinds: Vec<PieceKind>,
     pub board: Board,
+    /// Seats in turn order.
+    pub players: Vec<Player>,
+    /// Players winning together.
+    pub teams: Vec<Vec<Player>>,
     pub current_player: Player,
     pub move_history: History,
     pub finished: bool,
     pub winner: Option<Player>,
+    pub victory: Victory,
+    /// Captures made by each player, in `players` order.
+    pub captures: Vec<u8>,
+    /// Players left without moves.
+    pub eliminated: Vec<Player>,
+    /// Odds given in this game.
+    pub handicaps: Vec<Handicap>,
+    /// Seats played by programs rather than people.
+    pub bots: Vec<Player>,
+    /// Opening book moves from the position, if the server shares them.
+    pub explorer: Vec<BookMove>,
+    /// Hints each player may still ask for, in `players` order. Empty when
+    /// the game has no hints.
+    pub hints: Vec<u8>,
+    /// Move suggested to this client's player after asking for a hint.
+    pub hint: Option<Move>,
+    /// Players see only squares their pieces occupy or attack.
+    pub fog: bool,
+    /// Players move at the same time.
+    pub simultaneous: bool,
+    /// Players who submitted their move this turn in simultaneous games.
+    pub ready: Vec<Player>,
 }
 
 impl Default for GameState {
     fn default() -> GameState {
-        use crate::types::Piece::*;
-
-        let board = [
-            [Black, Empty, Black, Empty, Black, Empty, Black, Empty],
-            [Empty, Black, Empty, Black, Empty, Black, Empty, Black],
-            [Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty],
-            [Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty],
-            [Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty],
-            [Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty],
-            [White, Empty, White, Empty, White, Empty, White, Empty],
-            [Empty, White, Empty, White, Empty, White, Empty, White],
-        ];
+        let mut board = vec![vec![Piece::Empty; 8]; 8];
+        for (y, row) in board.iter_mut().enumerate() {
+            let owner = match y {
+                0 | 1 => Player::Black,
+                6 | 7 => Player::White,
+                _ => continue,
+            };
+            for (x, piece) in row.iter_mut().enumerate() {
+                if (x + y).is_multiple_of(2) {
+                    *piece = Piece::Leaper {
+                        kind: 0,
+                        owner: owner.clone(),
+                    };
+                }
+            }
+        }
         GameState {
+            variant: String::from("classic"),
+            width: 8,
+            height: 8,
+            topology: Topology::Bounded,
+            kinds: vec![PieceKind {
+                name: String::from("knight"),
+                symbol: 'N',
+                leaps: vec![(1, 2)],
+            }],
             board,
+            players: vec![Player::White, Player::Black],
+            teams: vec![vec![Player::White], vec![Player::Black]],
             current_player: Player::White,
             move_history: vec![],
             finished: false,
             winner: None,
+            victory: Victory::MinPieces(4),
+            captures: vec![0, 0],
+            eliminated: vec![],
+            handicaps: vec![],
+            bots: vec![],
+            explorer: vec![],
+            hints: vec![],
+            hint: None,
+            fog: false,
+            simultaneous: false,
+            ready: vec![],
         }
     }
 }
@@ -1,12 +1,22 @@
 mod gamestate;
+#[allow(clippy::module_inception)]
 mod types;
 mod board_view;
 
 pub use gamestate::GameState;
 pub use board_view::BoardView;
 pub use types::Piece;
+pub use types::PieceKind;
 pub use types::Player;
 pub use types::Position;
+pub use types::Request;
 pub use types::Move;
+pub use types::column_letter;
 pub use types::Board;
+pub use types::BookMove;
+pub use types::Difficulty;
+pub use types::Handicap;
+pub use types::Hello;
 pub use types::History;
+pub use types::Topology;
+pub use types::Victory;
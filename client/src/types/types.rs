@@ -1,26 +1,70 @@
 use serde::Deserialize;
 use serde::Serialize;
+use std::fmt;
+use std::str::FromStr;
+
+/// Kind of piece, defined by leaps it moves by.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PieceKind {
+    pub name: String,
+    pub symbol: char,
+    pub leaps: Vec<(u8, u8)>,
+}
+
+impl PieceKind {
+    /// Glyph of the piece: knights are drawn as chess knights,
+    /// other pieces as their letter, lowercase for Black and uppercase otherwise.
+    /// Red and Blue pieces are told apart by colour.
+    pub fn glyph(&self, owner: &Player) -> String {
+        match (self.symbol, owner) {
+            ('N', Player::White) => String::from("♘"),
+            ('N', _) => String::from("♞"),
+            (symbol, Player::Black) => symbol.to_lowercase().to_string(),
+            (symbol, _) => symbol.to_uppercase().to_string(),
+        }
+    }
+
+    /// All board offsets the piece can move by.
+    pub fn offsets(&self) -> Vec<(i32, i32)> {
+        let mut offsets = vec![];
+        for &(a, b) in &self.leaps {
+            for &(dx, dy) in &[(a, b), (b, a)] {
+                for &(sx, sy) in &[(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                    let offset = (sx * i32::from(dx), sy * i32::from(dy));
+                    if !offsets.contains(&offset) {
+                        offsets.push(offset);
+                    }
+                }
+            }
+        }
+        offsets
+    }
+}
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Piece {
     Empty,
-    White,
-    Black,
+    /// Square no piece can land on.
+    Blocked,
+    /// Square out of player's sight in fog games.
+    Hidden,
+    Leaper {
+        kind: u8,
+        owner: Player,
+    },
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Player {
     White,
     Black,
+    Red,
+    Blue,
 }
 
 impl PartialEq<Piece> for Player {
     fn eq(&self, piece: &Piece) -> bool {
-        match (self, piece) {
-            (Player::White, Piece::White) => true,
-            (Player::Black, Piece::Black) => true,
-            _ => false,
-        }
+        matches!(piece, Piece::Leaper { owner, .. } if owner == self)
     }
 }
 
@@ -43,6 +87,119 @@ pub struct Move {
     pub to: Position,
 }
 
+/// Move played from the position in the server's opening book.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BookMove {
+    pub from: Position,
+    pub to: Position,
+    pub games: u32,
+    /// Results for the player making the move.
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+/// How board edges are connected.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Topology {
+    Bounded,
+    /// Moves leaving the board continue from the opposite edge.
+    Toroidal,
+}
+
+/// Condition deciding the game winner.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Victory {
+    MinPieces(u8),
+    CaptureAll,
+    BackRank(u8),
+    KingOfTheHill,
+    Captures(u8),
+    Immobilize,
+}
+
+impl fmt::Display for Victory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Victory::MinPieces(count) => write!(f, "opponent has fewer than {} pieces", count),
+            Victory::CaptureAll => write!(f, "capture all pieces"),
+            Victory::BackRank(count) => write!(f, "{} pieces on opponent's home row", count),
+            Victory::KingOfTheHill => write!(f, "occupy the center"),
+            Victory::Captures(count) => write!(f, "first to {} captures", count),
+            Victory::Immobilize => write!(f, "leave opponent without moves"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Handicap {
+    Knights(Player, u8),
+    ExtraMoves(Player, u8),
+}
+
+impl fmt::Display for Handicap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Handicap::Knights(player, count) => {
+                write!(f, "{:?} plays without {} knights", player, count)
+            }
+            Handicap::ExtraMoves(player, count) => {
+                write!(f, "{:?} makes {} extra moves", player, count)
+            }
+        }
+    }
+}
+
+/// Strength of the server's engine.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Difficulty {
+    Beginner,
+    Easy,
+    Medium,
+    Hard,
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "beginner" => Ok(Difficulty::Beginner),
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!(
+                "Unknown difficulty '{}', expected one of: beginner, easy, medium, hard",
+                s
+            )),
+        }
+    }
+}
+
+/// Requests to the server, sent on the same line-based stream as moves.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Request {
+    /// Introduces the client, sent before anything else.
+    Hello(Hello),
+    /// Asks for the engine's move, in games with hints.
+    Hint(Player),
+}
+
+/// First line of a connection, without it the server waits a while before
+/// seating the client.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Hello {
+    pub name: String,
+    pub bot: bool,
+    /// Engine strength asked for, in games against the computer.
+    pub difficulty: Option<Difficulty>,
+}
+
+/// Letter naming the board column `x` ("a" for the leftmost one).
+pub fn column_letter(x: usize) -> String {
+    char::from(b'a' + x as u8).to_string()
+}
+
 pub type History = Vec<Move>;
 
-pub type Board = [[Piece; 8]; 8];
+pub type Board = Vec<Vec<Piece>>;
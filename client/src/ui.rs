@@ -10,13 +10,12 @@ pub struct Ui {
     pub cursive: Cursive,
     pub ui_rx: mpsc::Receiver<UiMessage>,
     pub ui_tx: mpsc::Sender<UiMessage>,
-    pub controller_tx: mpsc::Sender<ControllerMessage>,
     pub player: Option<Player>,
 }
 
 pub enum UiMessage {
     UpdateProfile(Option<Player>),
-    UpdateState(GameState),
+    UpdateState(Box<GameState>),
     ServerIsDown,
 }
 
@@ -27,21 +26,31 @@ impl Ui {
         let (ui_tx, ui_rx) = mpsc::channel::<UiMessage>();
         let mut ui = Ui {
             cursive: Cursive::default(),
-            ui_tx: ui_tx,
-            ui_rx: ui_rx,
-            controller_tx: controller_tx.clone(),
+            ui_tx,
+            ui_rx,
             player: None,
         };
 
         ui.cursive.set_fps(30);
         ui.cursive.add_global_callback(Key::Esc, move |c| c.quit());
-        ui.cursive.add_global_callback('h', move |c| show_help(c));
+        ui.cursive.add_global_callback('h', show_help);
+        let hint_tx = controller_tx.clone();
+        ui.cursive.add_global_callback('i', move |_| {
+            let _ = hint_tx.send(ControllerMessage::HintRequested);
+        });
         let gamestate = BoardView::new(controller_tx.clone());
 
         let screen_size = ui.cursive.screen_size();
         let right_panel = LinearLayout::vertical()
+            .child(TextView::new("Variant: None").with_id("variant"))
+            .child(TextView::new("Victory: None").with_id("victory"))
+            .child(TextView::new("Handicap: none").with_id("handicap"))
+            .child(TextView::new("Captures: 0 / 0").with_id("captures"))
+            .child(TextView::new("Pieces: None").with_id("pieces"))
             .child(TextView::new("Your color: None").with_id("profile"))
             .child(TextView::new("Current turn: None").with_id("current_turn"))
+            .child(TextView::new("Hints: none").with_id("hints"))
+            .child(TextView::new("Explorer: no book moves").with_id("explorer"))
             .child(TextView::new("<h> for help."))
             .child(
                 Dialog::new()
@@ -74,12 +83,9 @@ impl Ui {
         while let Some(message) = self.ui_rx.try_iter().next() {
             match message {
                 UiMessage::UpdateProfile(profile) => {
-                    let mut profile_type = "spectator";
+                    let mut profile_type = String::from("spectator");
                     if let Some(player) = profile.clone() {
-                        match player {
-                            Player::White => profile_type = "White",
-                            Player::Black => profile_type = "Black",
-                        }
+                        profile_type = format!("{:?}", player);
                         self.player = Some(player.clone());
                     }
                     self.cursive.call_on_id("board", |view: &mut BoardView| {
@@ -91,37 +97,151 @@ impl Ui {
                 }
                 UiMessage::UpdateState(new_state) => {
                     self.cursive.call_on_id("board", |view: &mut BoardView| {
-                        view.gamestate = new_state.clone();
+                        view.gamestate = (*new_state).clone();
+                    });
+                    self.cursive.call_on_id("variant", |view: &mut TextView| {
+                        let mut variant = new_state.variant.clone();
+                        if new_state.topology == Topology::Toroidal {
+                            variant.push_str(" (edges wrap around)");
+                        }
+                        if new_state.fog {
+                            variant.push_str(" (fog of war)");
+                        }
+                        view.set_content(format!("Variant: {}", variant))
+                    });
+                    self.cursive.call_on_id("victory", |view: &mut TextView| {
+                        view.set_content(format!("Victory: {}", new_state.victory))
+                    });
+                    self.cursive.call_on_id("handicap", |view: &mut TextView| {
+                        let handicaps: Vec<String> = new_state
+                            .handicaps
+                            .iter()
+                            .map(|handicap| handicap.to_string())
+                            .collect();
+                        if handicaps.is_empty() {
+                            view.set_content("Handicap: none")
+                        } else {
+                            view.set_content(format!("Handicap: {}", handicaps.join(", ")))
+                        }
+                    });
+                    self.cursive.call_on_id("pieces", |view: &mut TextView| {
+                        let kinds: Vec<String> = new_state
+                            .kinds
+                            .iter()
+                            .map(|kind| format!("{} {} {:?}", kind.symbol, kind.name, kind.leaps))
+                            .collect();
+                        view.set_content(format!("Pieces: {}", kinds.join(", ")))
+                    });
+                    self.cursive.call_on_id("captures", |view: &mut TextView| {
+                        let captures: Vec<String> = new_state
+                            .players
+                            .iter()
+                            .zip(&new_state.captures)
+                            .map(|(player, count)| {
+                                let mut text = format!("{:?} {}", player, count);
+                                if new_state.bots.contains(player) {
+                                    text.push_str(" (bot)");
+                                }
+                                if new_state.eliminated.contains(player) {
+                                    text.push_str(" (out)");
+                                }
+                                text
+                            })
+                            .collect();
+                        view.set_content(format!("Captures: {}", captures.join(" / ")))
                     });
                     self.cursive
                         .call_on_id("current_turn", |view: &mut TextView| {
-                            view.set_content(format!(
-                                "Current turn: {:?}",
-                                new_state.clone().current_player
-                            ))
+                            if new_state.simultaneous {
+                                view.set_content(format!(
+                                    "Current turn: everyone, ready: {:?}",
+                                    new_state.ready
+                                ))
+                            } else {
+                                view.set_content(format!(
+                                    "Current turn: {:?}",
+                                    new_state.clone().current_player
+                                ))
+                            }
                         });
+                    self.cursive.call_on_id("hints", |view: &mut TextView| {
+                        if new_state.hints.is_empty() {
+                            view.set_content("Hints: none");
+                            return;
+                        }
+                        let left: Vec<String> = new_state
+                            .players
+                            .iter()
+                            .zip(&new_state.hints)
+                            .map(|(player, count)| format!("{:?} {}", player, count))
+                            .collect();
+                        let mut text = format!("Hints left: {}", left.join(" / "));
+                        if let Some(hint) = &new_state.hint {
+                            text.push_str(&format!(
+                                "\n  Try {}{} -> {}{}",
+                                column_letter(hint.from.x as usize),
+                                hint.from.y,
+                                column_letter(hint.to.x as usize),
+                                hint.to.y
+                            ));
+                        }
+                        view.set_content(text)
+                    });
+                    self.cursive.call_on_id("explorer", |view: &mut TextView| {
+                        if new_state.explorer.is_empty() {
+                            view.set_content("Explorer: no book moves");
+                            return;
+                        }
+                        let mut text = String::from("Explorer:");
+                        for book_move in &new_state.explorer {
+                            text.push_str(&format!(
+                                "\n  {}{} -> {}{}: {} games, +{} ={} -{}",
+                                column_letter(book_move.from.x as usize),
+                                book_move.from.y,
+                                column_letter(book_move.to.x as usize),
+                                book_move.to.y,
+                                book_move.games,
+                                book_move.wins,
+                                book_move.draws,
+                                book_move.losses
+                            ));
+                        }
+                        view.set_content(text)
+                    });
                     self.cursive.call_on_id("history", |view: &mut ListView| {
                         view.clear();
-                        let letters = vec!["a", "b", "c", "d", "e", "f", "g", "h"];
                         for horse_move in new_state.clone().move_history {
                             let text = format!(
                                 "{:?} {}{} -> {}{}",
                                 horse_move.player,
-                                letters[horse_move.from.x as usize],
+                                column_letter(horse_move.from.x as usize),
                                 horse_move.from.y,
-                                letters[horse_move.to.x as usize],
-                                horse_move.from.x
+                                column_letter(horse_move.to.x as usize),
+                                horse_move.to.y
                             );
                             view.add_child(" ", TextView::new(text));
                         }
                     });
                     if new_state.finished {
-                        let mut message = String::from("Opponent was disconnected.");
+                        let mut message = String::from("Draw.");
                         let mut button_msg = String::from("Ok");
                         if let Some(winner) = new_state.winner {
-                            if None == self.player {
-                                message = format!("{:?} won.", winner);
-                            } else if Some(winner) == self.player {
+                            let winners = new_state
+                                .teams
+                                .iter()
+                                .find(|team| team.contains(&winner))
+                                .cloned()
+                                .unwrap_or_else(|| vec![winner.clone()]);
+                            if self.player.is_none() {
+                                let names: Vec<String> = winners
+                                    .iter()
+                                    .map(|player| format!("{:?}", player))
+                                    .collect();
+                                message = format!("{} won.", names.join(" & "));
+                            } else if winners
+                                .iter()
+                                .any(|player| Some(player) == self.player.as_ref())
+                            {
                                 message = String::from("You won!");
                                 button_msg = String::from("Yay!");
                             } else {
@@ -171,7 +291,11 @@ fn show_help(siv: &mut Cursive) {
         "
 <ESC>: close game.
 <h>: show help.
-'W': white horses.
-'B': black horses",
+<i>: ask for a hint, in games with hints.
+'♘'/'♞': white/black knights.
+Other pieces are shown by letter:
+uppercase for White, lowercase for Black.
+Dark squares are blocked.
+'?' squares are hidden by fog of war.",
     ));
 }
@@ -0,0 +1,80 @@
+#!/usr/bin/env python3
+"""Example bot for the external protocol (see src/external.rs): plays a random
+move, preferring captures where the variant allows them.
+
+Run it with `horse-server --bot black:external:bots/random_bot.py`.
+"""
+
+import json
+import random
+import sys
+
+# Variants where pieces only move onto empty squares.
+NO_CAPTURES = {"blockade"}
+
+
+def square(x, y):
+    return "{}{}".format(chr(ord("a") + x), y)
+
+
+def moves(state, player):
+    width, height = state["width"], state["height"]
+    teams = [team for team in state["teams"] if player in team]
+    allies = teams[0] if teams else [player]
+    board = state["board"]
+    can_capture = state["variant"] not in NO_CAPTURES
+    captures, quiet = [], []
+
+    for y, row in enumerate(board):
+        for x, piece in enumerate(row):
+            if not isinstance(piece, dict) or piece["Leaper"]["owner"] != player:
+                continue
+
+            for a, b in state["kinds"][piece["Leaper"]["kind"]]["leaps"]:
+                for dx, dy in {(a, b), (b, a), (-a, b), (-b, a), (a, -b), (b, -a), (-a, -b), (-b, -a)}:
+                    tx, ty = x + dx, y + dy
+                    if state["topology"] == "Toroidal":
+                        tx, ty = tx % width, ty % height
+                    elif not (0 <= tx < width and 0 <= ty < height):
+                        continue
+
+                    target = board[ty][tx]
+                    if target in ("Blocked", "Hidden"):
+                        continue
+                    if isinstance(target, dict):
+                        if not can_capture or target["Leaper"]["owner"] in allies:
+                            continue
+                        captures.append((x, y, tx, ty))
+                    else:
+                        quiet.append((x, y, tx, ty))
+
+    return captures or quiet
+
+
+def main():
+    player, state = None, None
+
+    for line in sys.stdin:
+        command, _, argument = line.strip().partition(" ")
+
+        if command == "horse":
+            print("id name random bot")
+            print("horseok")
+        elif command == "player":
+            player = argument.capitalize()
+        elif command == "position":
+            state = json.loads(argument)
+        elif command == "go":
+            candidates = moves(state, player)
+            print("info {} candidate moves".format(len(candidates)))
+            if candidates:
+                fx, fy, tx, ty = random.choice(candidates)
+                print("bestmove {} {}".format(square(fx, fy), square(tx, ty)))
+        elif command == "quit":
+            return
+
+        sys.stdout.flush()
+
+
+if __name__ == "__main__":
+    main()
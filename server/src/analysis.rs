@@ -0,0 +1,286 @@
+//! Post-game analysis: the engine searches every position of a finished
+//! game and judges each move by how much worse it left the mover than the
+//! engine's choice would have.
+
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Engine;
+use crate::gamesate::GameState;
+use crate::rules::Ruleset;
+use crate::types::*;
+
+/// Values are capped at ten pieces either way, winning slower than
+/// possible isn't a mistake.
+const CAP: i32 = 1000;
+
+/// How bad a move is.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
+pub enum Judgement {
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+    /// Judgement of a move leaving the mover `loss` worse off than the best
+    /// one, in evaluation units (a piece is usually worth 100).
+    fn of(loss: i32) -> Option<Judgement> {
+        match loss {
+            loss if loss >= 300 => Some(Judgement::Blunder),
+            loss if loss >= 100 => Some(Judgement::Mistake),
+            loss if loss >= 40 => Some(Judgement::Inaccuracy),
+            _ => None,
+        }
+    }
+
+    /// Annotation symbol, as in chess.
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Judgement::Inaccuracy => "?!",
+            Judgement::Mistake => "?",
+            Judgement::Blunder => "??",
+        }
+    }
+}
+
+impl fmt::Display for Judgement {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Judgement::Inaccuracy => "inaccuracy",
+            Judgement::Mistake => "mistake",
+            Judgement::Blunder => "blunder",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Move of the game with the engine's opinion of it.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Annotation {
+    pub mv: Move,
+    /// Value of the position for the mover with the best move, capped.
+    pub best_value: i32,
+    /// Value of the position for the mover after the move played, capped.
+    pub value: i32,
+    pub judgement: Option<Judgement>,
+    /// The engine's move, given for judged moves.
+    pub better: Option<Move>,
+}
+
+/// The engine's opinion of a position.
+enum Evaluation {
+    /// Game over, with the winner if any.
+    Over(Option<Player>),
+    /// Value for the player to move, capped, with the engine's move.
+    Searched {
+        mover: Player,
+        value: i32,
+        best: Option<Move>,
+    },
+}
+
+impl Evaluation {
+    /// Searches `state` with `engine`.
+    fn of<R: Ruleset>(engine: &Engine, state: &GameState<R>) -> Evaluation {
+        let mover = match state.to_move() {
+            Some(mover) if !state.is_finished() => mover,
+            _ => return Evaluation::Over(state.get_winner()),
+        };
+        let deadline = Instant::now() + engine.time_limit;
+        let result = engine.search(state, &mover, deadline, &AtomicBool::new(false));
+
+        Evaluation::Searched {
+            mover,
+            value: result.value.unwrap_or(0).clamp(-CAP, CAP),
+            best: result.best,
+        }
+    }
+
+    /// Value for `player`, whose allies are those of `state`.
+    fn value_for<R: Ruleset>(&self, state: &GameState<R>, player: &Player) -> i32 {
+        match self {
+            Evaluation::Over(Some(winner)) if state.allies(winner, player) => CAP,
+            Evaluation::Over(Some(_)) => -CAP,
+            Evaluation::Over(None) => 0,
+            Evaluation::Searched { mover, value, .. } if state.allies(mover, player) => *value,
+            Evaluation::Searched { value, .. } => -value,
+        }
+    }
+
+    /// The engine's move, if `player` is to move.
+    fn best_for(&self, player: &Player) -> Option<Move> {
+        match self {
+            Evaluation::Searched { mover, best, .. } if mover == player => best.clone(),
+            _ => None,
+        }
+    }
+}
+
+/// Annotates every move of `game`, which must have been played from the
+/// start of a variant where players take turns. Each position is searched
+/// once, its value after a move is the value before the next one.
+pub fn analyze<R: Ruleset>(
+    game: &GameState<R>,
+    engine: &Engine,
+) -> Result<Vec<Annotation>, String> {
+    if R::simultaneous() {
+        return Err(String::from(
+            "Games with simultaneous moves can't be analyzed",
+        ));
+    }
+
+    let mut state = game.restart()?;
+    let mut annotations = vec![];
+    let mut before = Evaluation::of(engine, &state);
+
+    for mv in game.get_history() {
+        state.make_move(mv.clone());
+        let after = Evaluation::of(engine, &state);
+
+        let best_value = before.value_for(&state, &mv.player);
+        let value = after.value_for(&state, &mv.player);
+        let best = before.best_for(&mv.player);
+        let judgement = match &best {
+            Some(best) if best != mv => Judgement::of(best_value - value),
+            _ => None,
+        };
+        annotations.push(Annotation {
+            mv: mv.clone(),
+            best_value,
+            value,
+            judgement,
+            better: best.filter(|_| judgement.is_some()),
+        });
+
+        before = after;
+    }
+
+    Ok(annotations)
+}
+
+/// Text record of `game` with `annotations`, one numbered move per line.
+pub fn record<R: Ruleset>(game: &GameState<R>, annotations: &[Annotation]) -> String {
+    let result = match game.get_winner() {
+        Some(winner) => format!("{:?} won", winner),
+        None => String::from("draw"),
+    };
+    let mut text = format!("{}, {}: {}\n", R::NAME, game.get_victory(), result);
+
+    for (ply, annotation) in annotations.iter().enumerate() {
+        let mv = &annotation.mv;
+        text.push_str(&format!(
+            "{}. {:?} {} -> {}",
+            ply + 1,
+            mv.player,
+            mv.from,
+            mv.to
+        ));
+
+        if let (Some(judgement), Some(better)) = (annotation.judgement, &annotation.better) {
+            text.push_str(&format!(
+                " {} {}, loses {:.1}, better {} -> {}",
+                judgement.symbol(),
+                judgement,
+                f64::from(annotation.best_value - annotation.value) / 100.0,
+                better.from,
+                better.to
+            ));
+        }
+        text.push('\n');
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Classic, Teams};
+    use crate::victory::Victory;
+
+    fn mv(player: Player, from: (u8, u8), to: (u8, u8)) -> Move {
+        Move {
+            player,
+            from: Position {
+                x: from.0,
+                y: from.1,
+            },
+            to: Position { x: to.0, y: to.1 },
+        }
+    }
+
+    #[test]
+    fn judgement_thresholds() {
+        assert_eq!(Judgement::of(-50), None);
+        assert_eq!(Judgement::of(0), None);
+        assert_eq!(Judgement::of(39), None);
+        assert_eq!(Judgement::of(40), Some(Judgement::Inaccuracy));
+        assert_eq!(Judgement::of(99), Some(Judgement::Inaccuracy));
+        assert_eq!(Judgement::of(100), Some(Judgement::Mistake));
+        assert_eq!(Judgement::of(299), Some(Judgement::Mistake));
+        assert_eq!(Judgement::of(300), Some(Judgement::Blunder));
+        assert_eq!(Judgement::of(2 * CAP), Some(Judgement::Blunder));
+    }
+
+    #[test]
+    fn values_are_seen_from_the_player() {
+        let state = GameState::<Classic>::new(8, 8);
+        let searched = Evaluation::Searched {
+            mover: Player::White,
+            value: 250,
+            best: None,
+        };
+
+        assert_eq!(searched.value_for(&state, &Player::White), 250);
+        assert_eq!(searched.value_for(&state, &Player::Black), -250);
+
+        let teams = GameState::<Teams>::new(8, 8);
+        assert_eq!(searched.value_for(&teams, &Player::Black), 250);
+        assert_eq!(searched.value_for(&teams, &Player::Red), -250);
+    }
+
+    #[test]
+    fn finished_games_are_capped() {
+        let state = GameState::<Classic>::new(8, 8);
+        let won = Evaluation::Over(Some(Player::Black));
+
+        assert_eq!(won.value_for(&state, &Player::Black), CAP);
+        assert_eq!(won.value_for(&state, &Player::White), -CAP);
+        assert_eq!(Evaluation::Over(None).value_for(&state, &Player::White), 0);
+        assert_eq!(won.best_for(&Player::Black), None);
+    }
+
+    #[test]
+    fn hanging_the_deciding_piece_is_a_blunder() {
+        let engine = Engine {
+            max_depth: 3,
+            ..Engine::default()
+        };
+        let mut game = GameState::<Classic>::new(8, 8).with_victory(Victory::Captures(1));
+        game.make_move(mv(Player::White, (6, 6), (5, 4)));
+        game.make_move(mv(Player::Black, (1, 1), (2, 3)));
+        let best = engine.best_move(&game, &Player::White).unwrap();
+        // Black's knight on (2, 3) takes it, winning the game.
+        game.make_move(mv(Player::White, (5, 4), (3, 5)));
+        game.make_move(mv(Player::Black, (2, 3), (3, 5)));
+        assert_eq!(game.get_winner(), Some(Player::Black));
+
+        let annotations = analyze(&game, &engine).unwrap();
+
+        assert_eq!(annotations.len(), 4);
+        assert_eq!(annotations[0].judgement, None);
+        assert_eq!(annotations[1].judgement, None);
+        assert_eq!(annotations[2].judgement, Some(Judgement::Blunder));
+        assert_eq!(annotations[2].value, -CAP);
+        assert_ne!(best, annotations[2].mv);
+        assert_eq!(annotations[2].better, Some(best));
+        assert_eq!(annotations[3].value, CAP);
+        assert_eq!(annotations[3].judgement, None);
+        assert!(record(&game, &annotations).contains("3. White f4 -> d5 ?? blunder"));
+    }
+}
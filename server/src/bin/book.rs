@@ -0,0 +1,242 @@
+//! Builds opening books from self-play and archived games, see
+//! `horse_server::book`.
+
+extern crate horse_server;
+extern crate rand;
+extern crate serde_json;
+
+use std::fs;
+use std::process;
+
+use rand::seq::SliceRandom;
+
+use horse_server::book::Book;
+use horse_server::engine::Difficulty;
+use horse_server::gamesate::GameState;
+use horse_server::options::parse_size;
+use horse_server::rules::*;
+use horse_server::with_variant;
+
+const USAGE: &str = "Usage: horse-book --output FILE [--variant NAME] [--width N] [--height N] \
+                     [--depth PLIES] [--selfplay N] [--difficulty LEVEL] [--random PLIES] \
+                     [--archive FILE]... [--min-games N]";
+
+/// Self-play games still running after this many moves are drawn.
+const MAX_PLIES: usize = 500;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    variant: String,
+    width: Option<u8>,
+    height: Option<u8>,
+    /// Moves of each game recorded in the book.
+    depth: usize,
+    selfplay: usize,
+    difficulty: Difficulty,
+    /// Random moves opening each self-play game, so games differ.
+    random: usize,
+    /// Files of games saved by `horse-server --archive`.
+    archives: Vec<String>,
+    /// Moves played in fewer games are left out.
+    min_games: u32,
+    output: String,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} requires a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{}: '{}' is not a number", flag, value))
+}
+
+fn parse_settings(mut args: impl Iterator<Item = String>) -> Result<Settings, String> {
+    let mut settings = Settings {
+        variant: Classic::NAME.to_string(),
+        width: None,
+        height: None,
+        depth: 12,
+        selfplay: 0,
+        difficulty: Difficulty::Easy,
+        random: 2,
+        archives: vec![],
+        min_games: 1,
+        output: String::new(),
+    };
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => output = Some(args.next().ok_or("--output requires a value")?),
+            "--variant" => {
+                settings.variant = args.next().ok_or("--variant requires a value")?;
+                if !VARIANTS.contains(&settings.variant.as_str()) {
+                    return Err(format!("Unknown variant '{}'", settings.variant));
+                }
+            }
+            "--width" => settings.width = Some(parse_size(&arg, args.next())?),
+            "--height" => settings.height = Some(parse_size(&arg, args.next())?),
+            "--depth" => settings.depth = parse_number(&arg, args.next())?,
+            "--selfplay" => settings.selfplay = parse_number(&arg, args.next())?,
+            "--difficulty" => {
+                settings.difficulty = args
+                    .next()
+                    .ok_or("--difficulty requires a value")?
+                    .parse()?
+            }
+            "--random" => settings.random = parse_number(&arg, args.next())?,
+            "--archive" => settings
+                .archives
+                .push(args.next().ok_or("--archive requires a value")?),
+            "--min-games" => settings.min_games = parse_number(&arg, args.next())?,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    settings.output = output.ok_or("--output is required")?;
+    if settings.selfplay == 0 && settings.archives.is_empty() {
+        return Err(String::from("--selfplay or --archive is required"));
+    }
+
+    Ok(settings)
+}
+
+/// Plays a game of the engine against itself, opening with `random`
+/// random moves, and records its first `depth` moves.
+fn selfplay<R: Ruleset>(book: &mut Book, settings: &Settings, (width, height): (u8, u8)) {
+    let engine = settings.difficulty.engine();
+    let mut rng = rand::thread_rng();
+    let mut state = GameState::<R>::new(width, height);
+    let mut moves = vec![];
+
+    for ply in 0..MAX_PLIES {
+        let player = match state.to_move() {
+            Some(player) => player,
+            None => break,
+        };
+        let mv = if ply < settings.random {
+            state.legal_moves_for(&player).choose(&mut rng).cloned()
+        } else {
+            engine.best_move(&state.view_for(Some(&player), false), &player)
+        };
+        let mv = match mv {
+            Some(mv) => mv,
+            None => break,
+        };
+
+        if ply < settings.depth {
+            moves.push((state.clone(), mv.clone()));
+        }
+        state.make_move(mv);
+    }
+
+    let winner = state.get_winner();
+    for (state, mv) in &moves {
+        book.add(state, mv, winner.as_ref());
+    }
+}
+
+/// Records the first `depth` moves of every finished game on a `size`
+/// board in `path`, returning how many were added.
+fn add_archive<R: Ruleset>(
+    book: &mut Book,
+    path: &str,
+    depth: usize,
+    size: (u8, u8),
+) -> Result<usize, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut added = 0;
+
+    for (number, line) in data.lines().enumerate() {
+        let skip = |reason: String| eprintln!("{}:{}: {}, skipped", path, number + 1, reason);
+
+        let saved: GameState<R> = match serde_json::from_str(line) {
+            Ok(saved) => saved,
+            Err(e) => {
+                skip(e.to_string());
+                continue;
+            }
+        };
+        if !saved.is_finished() {
+            continue;
+        }
+        let mut state = match saved.restart() {
+            Ok(state) => state,
+            Err(e) => {
+                skip(e);
+                continue;
+            }
+        };
+        if state.size() != size {
+            skip(String::from("another board size"));
+            continue;
+        }
+
+        let winner = saved.get_winner();
+        for (ply, mv) in saved.get_history().iter().enumerate() {
+            if ply < depth {
+                book.add(&state, mv, winner.as_ref());
+            }
+            state.make_move(mv.clone());
+        }
+        added += 1;
+    }
+
+    Ok(added)
+}
+
+fn run<R: Ruleset>(settings: &Settings) {
+    let (width, height) = R::default_size();
+    let size = (
+        settings.width.unwrap_or(width),
+        settings.height.unwrap_or(height),
+    );
+    if let Err(e) = check_size::<R>(size.0, size.1) {
+        eprintln!("{}", e);
+        process::exit(2);
+    }
+    let mut book = Book::new(R::NAME, size.0, size.1);
+
+    for game in 0..settings.selfplay {
+        selfplay::<R>(&mut book, settings, size);
+        println!(
+            "Self-play game {}/{}: {} positions",
+            game + 1,
+            settings.selfplay,
+            book.positions()
+        );
+    }
+
+    for path in &settings.archives {
+        match add_archive::<R>(&mut book, path, settings.depth, size) {
+            Ok(games) => println!("{}: {} games, {} positions", path, games, book.positions()),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    book.prune(settings.min_games);
+
+    if let Err(e) = book.save(&settings.output) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+    println!(
+        "Saved {} positions to {}",
+        book.positions(),
+        settings.output
+    );
+}
+
+fn main() {
+    let settings = match parse_settings(std::env::args().skip(1)) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    with_variant!(settings.variant.as_str(), run(&settings));
+}
@@ -0,0 +1,108 @@
+//! Generates endgame tablebases, see `horse_server::tablebase`.
+
+extern crate horse_server;
+
+use std::process;
+use std::time::Instant;
+
+use horse_server::tablebase::{Material, Tablebase};
+
+const USAGE: &str = "Usage: horse-tablebase --material WvB [--width N] [--height N] \
+                     [--min-pieces K] [--output FILE]";
+
+struct Settings {
+    width: u8,
+    height: u8,
+    min_pieces: u8,
+    material: Material,
+    output: String,
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<u8, String> {
+    let value = value.ok_or_else(|| format!("{} requires a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{}: '{}' is not a number", flag, value))
+}
+
+/// Parses material such as "4v5": White's knights, then Black's.
+fn parse_material(value: Option<String>) -> Result<Material, String> {
+    let value = value.ok_or("--material requires a value")?;
+    let mut counts = value.split('v').map(str::parse);
+
+    match (counts.next(), counts.next(), counts.next()) {
+        (Some(Ok(white)), Some(Ok(black)), None) => Ok((white, black)),
+        _ => Err(format!("'{}' is not material such as 4v5", value)),
+    }
+}
+
+fn parse_settings(mut args: impl Iterator<Item = String>) -> Result<Settings, String> {
+    let mut settings = Settings {
+        width: 8,
+        height: 8,
+        min_pieces: 4,
+        material: (0, 0),
+        output: String::new(),
+    };
+    let mut material = None;
+    let mut output = None;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--material" => material = Some(parse_material(args.next())?),
+            "--width" => settings.width = parse_number(&arg, args.next())?,
+            "--height" => settings.height = parse_number(&arg, args.next())?,
+            "--min-pieces" => settings.min_pieces = parse_number(&arg, args.next())?,
+            "--output" => output = Some(args.next().ok_or("--output requires a value")?),
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    settings.material = material.ok_or("--material is required")?;
+    settings.output = output.unwrap_or_else(|| {
+        format!(
+            "{}v{}-{}x{}.htb",
+            settings.material.0, settings.material.1, settings.width, settings.height
+        )
+    });
+
+    Ok(settings)
+}
+
+fn main() {
+    let settings = match parse_settings(std::env::args().skip(1)) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    let start = Instant::now();
+    let tablebase = Tablebase::generate(
+        settings.width,
+        settings.height,
+        settings.min_pieces,
+        settings.material,
+        |(white, black), positions| {
+            println!(
+                "Solving {}v{}: {} positions ({:.1}s)",
+                white,
+                black,
+                positions,
+                start.elapsed().as_secs_f64()
+            )
+        },
+    );
+
+    let result = tablebase.and_then(|tablebase| tablebase.save(&settings.output));
+    if let Err(e) = result {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+    println!(
+        "Saved {} ({:.1}s)",
+        settings.output,
+        start.elapsed().as_secs_f64()
+    );
+}
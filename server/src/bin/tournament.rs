@@ -0,0 +1,652 @@
+//! Plays many games between two engines and reports which is stronger.
+
+extern crate crossbeam;
+extern crate horse_server;
+extern crate rand;
+
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crossbeam::channel::unbounded;
+use rand::seq::SliceRandom;
+
+use horse_server::engine::{Difficulty, Engine};
+use horse_server::evaluation::Weights;
+use horse_server::external::External;
+use horse_server::gamesate::GameState;
+use horse_server::handicap::Handicap;
+use horse_server::mcts::Mcts;
+use horse_server::options::parse_size;
+use horse_server::rules::*;
+use horse_server::types::*;
+use horse_server::with_variant;
+
+const USAGE: &str = "Usage: horse-tournament --first ENGINE --second ENGINE [--variant NAME] \
+                     [--width N] [--height N] [--handicap HANDICAP]... [--games N] [--concurrency N] [--opening PLIES] [--movetime MS] \
+                     [--elo0 ELO] [--elo1 ELO] [--alpha A] [--beta B]\n\
+                     ENGINE is alpha-beta[:LEVEL[:EVAL_FILE]], mcts[:PLAYOUTS] or \
+                     external:COMMAND";
+
+/// Games still running after this many moves are drawn.
+const MAX_PLIES: usize = 500;
+/// Scores closer to 0 or 1 are reported as this far from it (about 1200 Elo).
+const MIN_SCORE: f64 = 0.001;
+
+/// Engine taking part in the tournament.
+#[derive(Debug, Clone)]
+enum Contestant {
+    /// With evaluation weights from a file written by `horse-tune`.
+    AlphaBeta(Difficulty, Option<String>),
+    Mcts(u32),
+    External(String),
+}
+
+impl Contestant {
+    fn parse(value: Option<String>) -> Result<Contestant, String> {
+        let value = value.ok_or("--first and --second require a value")?;
+        let mut parts = value.splitn(2, ':');
+
+        match (parts.next(), parts.next()) {
+            (Some("alpha-beta"), None) => Ok(Contestant::AlphaBeta(Difficulty::Hard, None)),
+            (Some("alpha-beta"), Some(level)) => {
+                let mut parts = level.splitn(2, ':');
+                let difficulty = parts.next().unwrap_or_default().parse()?;
+                let eval = parts.next().map(str::to_string);
+                if let Some(path) = &eval {
+                    Weights::load(path)?;
+                }
+                Ok(Contestant::AlphaBeta(difficulty, eval))
+            }
+            (Some("mcts"), None) => Ok(Contestant::Mcts(Mcts::default().playouts)),
+            (Some("mcts"), Some(playouts)) => playouts
+                .parse()
+                .ok()
+                .filter(|&playouts| playouts > 0)
+                .map(Contestant::Mcts)
+                .ok_or_else(|| format!("'{}' is not a positive number", playouts)),
+            (Some("external"), Some(command)) if !command.trim().is_empty() => {
+                Ok(Contestant::External(command.to_string()))
+            }
+            _ => Err(format!("Unknown engine '{}'", value)),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Contestant::AlphaBeta(difficulty, None) => format!("alpha-beta:{}", difficulty),
+            Contestant::AlphaBeta(difficulty, Some(eval)) => {
+                format!("alpha-beta:{}:{}", difficulty, eval)
+            }
+            Contestant::Mcts(playouts) => format!("mcts:{}", playouts),
+            Contestant::External(command) => command.clone(),
+        }
+    }
+
+    /// Gets ready to play `player` in a new game.
+    fn seat(&self, player: &Player) -> Result<Seat, String> {
+        Ok(match self {
+            Contestant::AlphaBeta(difficulty, eval) => Seat::AlphaBeta(Engine {
+                weights: match eval {
+                    Some(path) => Weights::load(path)?,
+                    None => Weights::default(),
+                },
+                ..difficulty.engine()
+            }),
+            Contestant::Mcts(playouts) => Seat::Mcts(Mcts {
+                playouts: *playouts,
+                ..Mcts::default()
+            }),
+            Contestant::External(command) => Seat::External(External::spawn(command, player)?),
+        })
+    }
+}
+
+/// Contestant playing a game.
+enum Seat {
+    AlphaBeta(Engine),
+    Mcts(Mcts),
+    External(External),
+}
+
+impl Seat {
+    fn best_move<R: Ruleset>(
+        &mut self,
+        state: &GameState<R>,
+        player: &Player,
+        movetime: Duration,
+    ) -> Result<Option<Move>, String> {
+        match self {
+            Seat::AlphaBeta(engine) => Ok(engine.best_move(state, player)),
+            Seat::Mcts(mcts) => Ok(mcts.best_move(state, player)),
+            Seat::External(bot) => bot.best_move(state, player, movetime).map(Some),
+        }
+    }
+}
+
+/// Sequential probability ratio test of H0: elo = elo0 against H1: elo = elo1.
+#[derive(Debug, Clone)]
+struct Sprt {
+    elo0: f64,
+    elo1: f64,
+    /// Probability of accepting H1 when H0 holds.
+    alpha: f64,
+    /// Probability of accepting H0 when H1 holds.
+    beta: f64,
+}
+
+impl Sprt {
+    /// Log-likelihood ratio bounds accepting H0 and H1.
+    fn bounds(&self) -> (f64, f64) {
+        (
+            (self.beta / (1.0 - self.alpha)).ln(),
+            ((1.0 - self.beta) / self.alpha).ln(),
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Settings {
+    variant: String,
+    width: Option<u8>,
+    height: Option<u8>,
+    /// Odds given in every game, factored into the Elo difference.
+    handicaps: Vec<Handicap>,
+    /// Games played, rounded up to pairs with the same opening.
+    games: usize,
+    concurrency: usize,
+    /// Random moves played before the engines take over.
+    opening: usize,
+    /// Time per move of external bots.
+    movetime: Duration,
+    sprt: Sprt,
+    first: Contestant,
+    second: Contestant,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} requires a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{}: '{}' is not a number", flag, value))
+}
+
+fn parse_settings(mut args: impl Iterator<Item = String>) -> Result<Settings, String> {
+    let (mut first, mut second) = (None, None);
+    let mut variant = Classic::NAME.to_string();
+    let (mut width, mut height) = (None, None);
+    let mut handicaps = vec![];
+    let (mut games, mut concurrency, mut opening, mut movetime) = (100, 1, 4, 100);
+    let mut sprt = Sprt {
+        elo0: 0.0,
+        elo1: 10.0,
+        alpha: 0.05,
+        beta: 0.05,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--first" => first = Some(Contestant::parse(args.next())?),
+            "--second" => second = Some(Contestant::parse(args.next())?),
+            "--variant" => {
+                variant = args.next().ok_or("--variant requires a value")?;
+                if !VARIANTS.contains(&variant.as_str()) {
+                    return Err(format!("Unknown variant '{}'", variant));
+                }
+            }
+            "--width" => width = Some(parse_size(&arg, args.next())?),
+            "--height" => height = Some(parse_size(&arg, args.next())?),
+            "--handicap" => {
+                handicaps.push(args.next().ok_or("--handicap requires a value")?.parse()?)
+            }
+            "--games" => games = parse_number(&arg, args.next())?,
+            "--concurrency" => concurrency = parse_number(&arg, args.next())?,
+            "--opening" => opening = parse_number(&arg, args.next())?,
+            "--movetime" => movetime = parse_number(&arg, args.next())?,
+            "--elo0" => sprt.elo0 = parse_number(&arg, args.next())?,
+            "--elo1" => sprt.elo1 = parse_number(&arg, args.next())?,
+            "--alpha" => sprt.alpha = parse_number(&arg, args.next())?,
+            "--beta" => sprt.beta = parse_number(&arg, args.next())?,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    if games == 0 || concurrency == 0 {
+        return Err(String::from("--games and --concurrency must be positive"));
+    }
+
+    Ok(Settings {
+        variant,
+        width,
+        height,
+        handicaps,
+        games,
+        concurrency,
+        opening,
+        movetime: Duration::from_millis(movetime),
+        sprt,
+        first: first.ok_or("--first is required")?,
+        second: second.ok_or("--second is required")?,
+    })
+}
+
+/// Result of a game for the first contestant.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Outcome {
+    Win,
+    Draw,
+    Loss,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    wins: u32,
+    draws: u32,
+    losses: u32,
+    /// Elo the handicaps cost the first contestant, summed over games.
+    odds: f64,
+}
+
+impl Stats {
+    fn add(&mut self, outcome: Outcome, odds: f64) {
+        self.odds += odds;
+        match outcome {
+            Outcome::Win => self.wins += 1,
+            Outcome::Draw => self.draws += 1,
+            Outcome::Loss => self.losses += 1,
+        }
+    }
+
+    fn games(&self) -> u32 {
+        self.wins + self.draws + self.losses
+    }
+
+    /// Average points per game, a draw counting half.
+    fn score(&self) -> f64 {
+        (f64::from(self.wins) + f64::from(self.draws) / 2.0) / f64::from(self.games())
+    }
+
+    /// Variance of the points of a single game.
+    fn variance(&self) -> f64 {
+        let score = self.score();
+        (f64::from(self.wins) * (1.0 - score).powi(2)
+            + f64::from(self.draws) * (0.5 - score).powi(2)
+            + f64::from(self.losses) * score.powi(2))
+            / f64::from(self.games())
+    }
+
+    /// Elo the handicaps cost the first contestant per game.
+    fn odds(&self) -> f64 {
+        self.odds / f64::from(self.games())
+    }
+
+    /// Elo difference, handicaps factored in, and its 95% confidence margin.
+    fn elo(&self) -> (f64, f64) {
+        let score = self.score();
+        let error = (self.variance() / f64::from(self.games())).sqrt();
+        let margin = (elo(score + 1.96 * error) - elo(score - 1.96 * error)) / 2.0;
+
+        (elo(score) + self.odds(), margin)
+    }
+
+    /// Log-likelihood ratio of `sprt`'s hypotheses, using the normal
+    /// approximation of the score. It stays 0 until results differ.
+    fn llr(&self, sprt: &Sprt) -> f64 {
+        let variance = self.variance();
+        if variance == 0.0 {
+            return 0.0;
+        }
+
+        // The hypotheses are about the contestants, not the odds given.
+        let (score0, score1) = (
+            expected_score(sprt.elo0 - self.odds()),
+            expected_score(sprt.elo1 - self.odds()),
+        );
+        f64::from(self.games()) * (score1 - score0) * (2.0 * self.score() - score0 - score1)
+            / (2.0 * variance)
+    }
+}
+
+/// Elo difference giving `score` points per game, capped for perfect scores.
+fn elo(score: f64) -> f64 {
+    let score = score.clamp(MIN_SCORE, 1.0 - MIN_SCORE);
+    -400.0 * (1.0 / score - 1.0).log10()
+}
+
+/// Points per game expected with `elo` difference.
+fn expected_score(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+/// Plays `opening` random moves from `start`, retrying if the game ends.
+fn random_opening<R: Ruleset>(opening: usize, start: &GameState<R>) -> GameState<R> {
+    let mut rng = rand::thread_rng();
+
+    loop {
+        let mut state = start.clone();
+
+        for _ in 0..opening {
+            let mv = state
+                .to_move()
+                .and_then(|player| state.legal_moves_for(&player).choose(&mut rng).cloned());
+            match mv {
+                Some(mv) => state.make_move(mv),
+                None => break,
+            }
+        }
+
+        if !state.is_finished() {
+            return state;
+        }
+    }
+}
+
+/// Plays a game from `state` and returns the winner. A contestant
+/// failing to start or to move forfeits.
+fn play<R: Ruleset>(
+    mut state: GameState<R>,
+    contestants: &[(Player, &Contestant)],
+    movetime: Duration,
+) -> Option<Player> {
+    let mut seats = vec![];
+    for (player, contestant) in contestants {
+        match contestant.seat(player) {
+            Ok(seat) => seats.push(seat),
+            Err(e) => {
+                eprintln!("{}", e);
+                return Some(player.opponent());
+            }
+        }
+    }
+
+    for _ in 0..MAX_PLIES {
+        let player = match state.to_move() {
+            Some(player) => player,
+            None => return state.get_winner(),
+        };
+        let seat = contestants
+            .iter()
+            .position(|(seated, _)| *seated == player)
+            .expect("every player is seated");
+        let view = state.view_for(Some(&player), false);
+
+        match seats[seat].best_move(&view, &player, movetime) {
+            Ok(Some(mv)) => state.make_move(mv),
+            Ok(None) => return Some(player.opponent()),
+            Err(e) => {
+                eprintln!("{}", e);
+                return Some(player.opponent());
+            }
+        }
+    }
+
+    None
+}
+
+fn run<R: Ruleset>(settings: &Settings) {
+    let players = R::players();
+    if players.len() != 2 {
+        eprintln!("Tournaments are played in two-player variants only");
+        process::exit(2);
+    }
+
+    let (width, height) = R::default_size();
+    let size = (
+        settings.width.unwrap_or(width),
+        settings.height.unwrap_or(height),
+    );
+    let start = check_size::<R>(size.0, size.1).and_then(|()| {
+        settings
+            .handicaps
+            .iter()
+            .try_fold(GameState::<R>::new(size.0, size.1), |state, handicap| {
+                state.with_handicap(handicap.clone())
+            })
+    });
+    let start = match start {
+        Ok(start) => start,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+
+    let (first, second) = (settings.first.name(), settings.second.name());
+    let pairs = settings.games.div_ceil(2);
+    let next_pair = AtomicUsize::new(0);
+    let stop = AtomicBool::new(false);
+    let (results_writer, results) = unbounded();
+    let (lower, upper) = settings.sprt.bounds();
+
+    println!(
+        "{} vs {}: {} games of {} on {}x{}, {} random opening moves",
+        first,
+        second,
+        pairs * 2,
+        R::NAME,
+        size.0,
+        size.1,
+        settings.opening
+    );
+    for handicap in &settings.handicaps {
+        println!("Handicap: {}", handicap);
+    }
+
+    crossbeam::scope(|scope| {
+        for _ in 0..settings.concurrency {
+            let results_writer = results_writer.clone();
+            let (next_pair, stop, players, start) = (&next_pair, &stop, &players, &start);
+
+            scope.spawn(move |_| {
+                while !stop.load(Ordering::Relaxed)
+                    && next_pair.fetch_add(1, Ordering::Relaxed) < pairs
+                {
+                    let opening = random_opening::<R>(settings.opening, start);
+
+                    // Each opening is played twice, with sides swapped.
+                    for first_seat in players {
+                        let contestants: Vec<_> = players
+                            .iter()
+                            .map(|player| {
+                                if player == first_seat {
+                                    (player.clone(), &settings.first)
+                                } else {
+                                    (player.clone(), &settings.second)
+                                }
+                            })
+                            .collect();
+
+                        let winner = play(opening.clone(), &contestants, settings.movetime);
+                        let outcome = match winner {
+                            Some(winner) if winner == *first_seat => Outcome::Win,
+                            Some(_) => Outcome::Loss,
+                            None => Outcome::Draw,
+                        };
+                        if results_writer.send((first_seat.clone(), outcome)).is_err() {
+                            return;
+                        }
+                    }
+                }
+            });
+        }
+        drop(results_writer);
+
+        let mut stats = Stats::default();
+        for (first_seat, outcome) in results.iter() {
+            let odds = settings
+                .handicaps
+                .iter()
+                .map(|handicap| handicap.odds(&first_seat))
+                .sum();
+            stats.add(outcome, odds);
+            let result = match outcome {
+                Outcome::Win => "1-0",
+                Outcome::Draw => "1/2-1/2",
+                Outcome::Loss => "0-1",
+            };
+            println!(
+                "Game {}: {} as {:?}: {} (+{} ={} -{})",
+                stats.games(),
+                first,
+                first_seat,
+                result,
+                stats.wins,
+                stats.draws,
+                stats.losses
+            );
+
+            let llr = stats.llr(&settings.sprt);
+            if llr <= lower || llr >= upper {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }
+
+        let (elo, margin) = stats.elo();
+        let llr = stats.llr(&settings.sprt);
+        println!(
+            "Score of {} vs {}: {} - {} - {} [{:.3}] {}",
+            first,
+            second,
+            stats.wins,
+            stats.losses,
+            stats.draws,
+            stats.score(),
+            stats.games()
+        );
+        println!("Elo difference: {:.1} +/- {:.1}", elo, margin);
+        if !settings.handicaps.is_empty() {
+            println!(
+                "Handicaps cost {} {:.1} Elo per game, factored in",
+                first,
+                stats.odds()
+            );
+        }
+        println!(
+            "SPRT ({:.1}, {:.1}): LLR {:.2} ({:.2}, {:.2}), {}",
+            settings.sprt.elo0,
+            settings.sprt.elo1,
+            llr,
+            lower,
+            upper,
+            if llr >= upper {
+                "H1 accepted"
+            } else if llr <= lower {
+                "H0 accepted"
+            } else {
+                "inconclusive"
+            }
+        );
+    })
+    .expect("game thread panicked");
+}
+
+fn main() {
+    let settings = match parse_settings(std::env::args().skip(1)) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    with_variant!(settings.variant.as_str(), run(&settings));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-6,
+            "{} != {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn elo_of_score() {
+        assert_close(elo(0.5), 0.0);
+        assert_close(elo(0.75), 190.848501887);
+        assert_close(elo(0.25), -190.848501887);
+        // Perfect scores are capped at 999 to 1.
+        assert_close(elo(1.0), 1199.826195290);
+        assert_close(elo(0.0), -1199.826195290);
+
+        assert_close(expected_score(0.0), 0.5);
+        assert_close(expected_score(190.848501887), 0.75);
+    }
+
+    #[test]
+    fn stats() {
+        let stats = Stats {
+            wins: 60,
+            draws: 20,
+            losses: 20,
+            odds: 0.0,
+        };
+        assert_close(stats.score(), 0.7);
+        assert_close(stats.variance(), 0.16);
+
+        let (elo, margin) = stats.elo();
+        assert_close(elo, 147.190714117);
+        assert_close(margin, 66.014638628);
+    }
+
+    #[test]
+    fn llr() {
+        let sprt = Sprt {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        };
+        let (lower, upper) = sprt.bounds();
+        assert_close(lower, -2.944438979);
+        assert_close(upper, 2.944438979);
+
+        let stats = Stats {
+            wins: 60,
+            draws: 20,
+            losses: 20,
+            odds: 0.0,
+        };
+        assert_close(stats.llr(&sprt), 1.733713311);
+
+        // Nothing to tell from identical results.
+        let draws = Stats {
+            wins: 0,
+            draws: 10,
+            losses: 0,
+            odds: 0.0,
+        };
+        assert_close(draws.llr(&sprt), 0.0);
+    }
+
+    #[test]
+    fn handicaps_are_factored_in() {
+        let knight = "white:knights:1".parse::<Handicap>().unwrap();
+        assert_close(knight.odds(&Player::White), 200.0);
+        assert_close(knight.odds(&Player::Black), -200.0);
+        let moves = "black:moves:2".parse::<Handicap>().unwrap();
+        assert_close(moves.odds(&Player::Black), -100.0);
+
+        // Even results while giving a knight in every game.
+        let mut stats = Stats::default();
+        stats.add(Outcome::Win, 200.0);
+        stats.add(Outcome::Loss, 200.0);
+        let (elo, _) = stats.elo();
+        assert_close(elo, 200.0);
+
+        // The hypotheses are shifted by the odds: scoring 0.5 while giving
+        // 200 Elo supports an engine 10 Elo stronger.
+        let sprt = Sprt {
+            elo0: 0.0,
+            elo1: 10.0,
+            alpha: 0.05,
+            beta: 0.05,
+        };
+        assert!(stats.llr(&sprt) > 0.0);
+    }
+}
@@ -0,0 +1,297 @@
+//! Tunes the evaluation weights of the alpha-beta engine on archived games,
+//! see `horse_server::evaluation`.
+//!
+//! Texel's method: every quiet position of the games gets the probability
+//! of the first player winning predicted as `1 / (1 + e^(-k * eval))`, and
+//! weights are changed one step at a time while the squared error between
+//! predictions and actual results decreases.
+
+extern crate horse_server;
+extern crate serde_json;
+
+use std::fs;
+use std::process;
+
+use horse_server::evaluation::{Features, Weights};
+use horse_server::gamesate::GameState;
+use horse_server::options::parse_size;
+use horse_server::rules::*;
+use horse_server::with_variant;
+
+const USAGE: &str = "Usage: horse-tune --archive FILE... [--variant NAME] [--width N] \
+                     [--height N] [--weights FILE] [--skip PLIES] [--output FILE]";
+
+const PARAMETERS: [&str; 4] = ["material", "mobility", "centralization", "clusters"];
+/// First step a weight is changed by, halved whenever no change helps.
+const FIRST_STEP: i32 = 16;
+
+#[derive(Debug, Clone)]
+struct Settings {
+    variant: String,
+    width: Option<u8>,
+    height: Option<u8>,
+    /// Files of games saved by `horse-server --archive`.
+    archives: Vec<String>,
+    /// Weights to start from.
+    weights: Weights,
+    /// Opening moves of each game left out.
+    skip: usize,
+    output: String,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = value.ok_or_else(|| format!("{} requires a value", flag))?;
+    value
+        .parse()
+        .map_err(|_| format!("{}: '{}' is not a number", flag, value))
+}
+
+fn parse_settings(mut args: impl Iterator<Item = String>) -> Result<Settings, String> {
+    let mut settings = Settings {
+        variant: Classic::NAME.to_string(),
+        width: None,
+        height: None,
+        archives: vec![],
+        weights: Weights::default(),
+        skip: 4,
+        output: String::from("weights.json"),
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--archive" => settings
+                .archives
+                .push(args.next().ok_or("--archive requires a value")?),
+            "--variant" => {
+                settings.variant = args.next().ok_or("--variant requires a value")?;
+                if !VARIANTS.contains(&settings.variant.as_str()) {
+                    return Err(format!("Unknown variant '{}'", settings.variant));
+                }
+            }
+            "--width" => settings.width = Some(parse_size(&arg, args.next())?),
+            "--height" => settings.height = Some(parse_size(&arg, args.next())?),
+            "--weights" => {
+                settings.weights = Weights::load(&args.next().ok_or("--weights requires a value")?)?
+            }
+            "--skip" => settings.skip = parse_number(&arg, args.next())?,
+            "--output" => settings.output = args.next().ok_or("--output requires a value")?,
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    if settings.archives.is_empty() {
+        return Err(String::from("--archive is required"));
+    }
+
+    Ok(settings)
+}
+
+/// Position of an archived game: features of both players and the points
+/// the first one scored in the end.
+struct Sample {
+    first: Features,
+    second: Features,
+    result: f64,
+}
+
+/// Quiet positions of the finished games on a `size` board in `path`,
+/// leaving out the first `skip` of each game.
+fn load_samples<R: Ruleset>(
+    path: &str,
+    skip: usize,
+    size: (u8, u8),
+    samples: &mut Vec<Sample>,
+) -> Result<usize, String> {
+    let data = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut games = 0;
+
+    for (number, line) in data.lines().enumerate() {
+        let skipped = |reason: String| eprintln!("{}:{}: {}, skipped", path, number + 1, reason);
+
+        let saved: GameState<R> = match serde_json::from_str(line) {
+            Ok(saved) => saved,
+            Err(e) => {
+                skipped(e.to_string());
+                continue;
+            }
+        };
+        if !saved.is_finished() {
+            continue;
+        }
+        let mut state = match saved.restart() {
+            Ok(state) => state,
+            Err(e) => {
+                skipped(e);
+                continue;
+            }
+        };
+        if state.size() != size {
+            skipped(String::from("another board size"));
+            continue;
+        }
+
+        let players = state.players().to_vec();
+        let result = match saved.get_winner() {
+            Some(winner) if state.allies(&winner, &players[0]) => 1.0,
+            Some(_) => 0.0,
+            None => 0.5,
+        };
+
+        for (ply, mv) in saved.get_history().iter().enumerate() {
+            // Positions where captures are possible are about to change,
+            // their static value predicts little.
+            let quiet = state.to_move().is_some_and(|mover| {
+                !state
+                    .legal_moves_for(&mover)
+                    .iter()
+                    .any(|mv| state.is_capture(mv))
+            });
+            if ply >= skip && quiet {
+                samples.push(Sample {
+                    first: Features::of(&state, &players[0]),
+                    second: Features::of(&state, &players[1]),
+                    result,
+                });
+            }
+            state.make_move(mv.clone());
+        }
+        games += 1;
+    }
+
+    Ok(games)
+}
+
+fn parameter(weights: &mut Weights, index: usize) -> &mut i32 {
+    match index {
+        0 => &mut weights.material,
+        1 => &mut weights.mobility,
+        2 => &mut weights.centralization,
+        _ => &mut weights.clusters,
+    }
+}
+
+/// Mean squared error of the predicted results.
+fn error(samples: &[Sample], weights: &Weights, k: f64) -> f64 {
+    let total: f64 = samples
+        .iter()
+        .map(|sample| {
+            let eval = weights.value(&sample.first) - weights.value(&sample.second);
+            let predicted = 1.0 / (1.0 + (-k * f64::from(eval)).exp());
+            (sample.result - predicted).powi(2)
+        })
+        .sum();
+
+    total / samples.len() as f64
+}
+
+/// Scaling `k` fitting the starting weights best, found by ternary search
+/// on its logarithm.
+fn fit_scaling(samples: &[Sample], weights: &Weights) -> f64 {
+    let (mut low, mut high) = (1e-6f64.ln(), 1f64.ln());
+
+    for _ in 0..100 {
+        let third = (high - low) / 3.0;
+        if error(samples, weights, (low + third).exp())
+            < error(samples, weights, (high - third).exp())
+        {
+            high -= third;
+        } else {
+            low += third;
+        }
+    }
+
+    ((low + high) / 2.0).exp()
+}
+
+/// Changes one weight at a time by `step` while that lowers the error,
+/// halving the step when nothing does.
+fn tune(samples: &[Sample], mut weights: Weights, k: f64) -> Weights {
+    let mut best = error(samples, &weights, k);
+    let mut step = FIRST_STEP;
+
+    while step > 0 {
+        let mut improved = false;
+
+        for index in 0..PARAMETERS.len() {
+            for &delta in &[step, -step] {
+                let mut candidate = weights.clone();
+                *parameter(&mut candidate, index) += delta;
+
+                let candidate_error = error(samples, &candidate, k);
+                if candidate_error < best {
+                    best = candidate_error;
+                    weights = candidate;
+                    improved = true;
+                    break;
+                }
+            }
+        }
+
+        println!("Step {}: error {:.6}, {:?}", step, best, weights);
+        if !improved {
+            step /= 2;
+        }
+    }
+
+    weights
+}
+
+fn run<R: Ruleset>(settings: &Settings) {
+    if R::players().len() != 2 {
+        eprintln!("Weights are tuned in two-player variants only");
+        process::exit(2);
+    }
+
+    let (width, height) = R::default_size();
+    let size = (
+        settings.width.unwrap_or(width),
+        settings.height.unwrap_or(height),
+    );
+    if let Err(e) = check_size::<R>(size.0, size.1) {
+        eprintln!("{}", e);
+        process::exit(2);
+    }
+
+    let mut samples = vec![];
+    for path in &settings.archives {
+        match load_samples::<R>(path, settings.skip, size, &mut samples) {
+            Ok(games) => println!("{}: {} games, {} positions", path, games, samples.len()),
+            Err(e) => {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+    }
+    if samples.is_empty() {
+        eprintln!("No positions to tune on");
+        process::exit(1);
+    }
+
+    let k = fit_scaling(&samples, &settings.weights);
+    println!(
+        "Scaling {:.6}, error {:.6} with {:?}",
+        k,
+        error(&samples, &settings.weights, k),
+        settings.weights
+    );
+
+    let weights = tune(&samples, settings.weights.clone(), k);
+
+    if let Err(e) = weights.save(&settings.output) {
+        eprintln!("{}", e);
+        process::exit(1);
+    }
+    println!("Saved {:?} to {}", weights, settings.output);
+}
+
+fn main() {
+    let settings = match parse_settings(std::env::args().skip(1)) {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    with_variant!(settings.variant.as_str(), run(&settings));
+}
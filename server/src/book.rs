@@ -0,0 +1,310 @@
+//! Opening books: moves played from positions early in past games, with
+//! how often each was played and how those games ended.
+//!
+//! Positions are keyed by `GameState::hash`. The file holds a header with
+//! the variant and board size, then entries sorted by key, each with the
+//! key (8 bytes), the move's squares (4 bytes) and the games, wins, draws
+//! and losses of the player making the move (4 bytes each).
+
+use std::fs;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::gamesate::GameState;
+use crate::rules::Ruleset;
+use crate::types::*;
+
+/// Identifies book files.
+const MAGIC: &[u8; 4] = b"HBK1";
+const ENTRY_SIZE: usize = 8 + 4 + 4 * 4;
+
+/// Move played from a book position.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct BookMove {
+    pub from: Position,
+    pub to: Position,
+    pub games: u32,
+    /// Results for the player making the move.
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+}
+
+impl BookMove {
+    /// Chance of being picked by `Book::pick`: points scored with the
+    /// move, a draw counting half, plus one so every move stays possible.
+    fn weight(&self) -> u32 {
+        2 * self.wins + self.draws + 1
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Book {
+    variant: String,
+    width: u8,
+    height: u8,
+    /// Sorted by key, then squares.
+    entries: Vec<(u64, BookMove)>,
+}
+
+impl Book {
+    pub fn new(variant: &str, width: u8, height: u8) -> Book {
+        Book {
+            variant: variant.to_string(),
+            width,
+            height,
+            entries: vec![],
+        }
+    }
+
+    /// Positions in the book.
+    pub fn positions(&self) -> usize {
+        let mut keys: Vec<u64> = self.entries.iter().map(|(key, _)| *key).collect();
+        keys.dedup();
+        keys.len()
+    }
+
+    /// Records `mv` played in `state` in a game `winner` won (`None` for a draw).
+    pub fn add<R: Ruleset>(&mut self, state: &GameState<R>, mv: &Move, winner: Option<&Player>) {
+        let key = state.hash();
+        let found = self.entries.binary_search_by(|(k, entry)| {
+            (*k, entry.from.x, entry.from.y, entry.to.x, entry.to.y)
+                .cmp(&(key, mv.from.x, mv.from.y, mv.to.x, mv.to.y))
+        });
+        let index = found.unwrap_or_else(|index| {
+            let entry = BookMove {
+                from: mv.from.clone(),
+                to: mv.to.clone(),
+                games: 0,
+                wins: 0,
+                draws: 0,
+                losses: 0,
+            };
+            self.entries.insert(index, (key, entry));
+            index
+        });
+
+        let entry = &mut self.entries[index].1;
+        entry.games += 1;
+        match winner {
+            Some(winner) if state.allies(winner, &mv.player) => entry.wins += 1,
+            Some(_) => entry.losses += 1,
+            None => entry.draws += 1,
+        }
+    }
+
+    /// Drops moves played in fewer than `games` games.
+    pub fn prune(&mut self, games: u32) {
+        self.entries.retain(|(_, entry)| entry.games >= games);
+    }
+
+    /// Moves played from `state`, most played first.
+    pub fn moves<R: Ruleset>(&self, state: &GameState<R>) -> Vec<BookMove> {
+        if R::NAME != self.variant || state.size() != (self.width, self.height) {
+            return vec![];
+        }
+
+        let key = state.hash();
+        let start = self.entries.partition_point(|(k, _)| *k < key);
+        let mut moves: Vec<BookMove> = self.entries[start..]
+            .iter()
+            .take_while(|(k, _)| *k == key)
+            .map(|(_, entry)| entry.clone())
+            .collect();
+        moves.sort_by_key(|entry| std::cmp::Reverse(entry.games));
+
+        moves
+    }
+
+    /// Random book move for `player`, moves scoring better picked more often.
+    pub fn pick<R: Ruleset>(&self, state: &GameState<R>, player: &Player) -> Option<Move> {
+        let legal = state.legal_moves_for(player);
+        let moves: Vec<(Move, u32)> = self
+            .moves(state)
+            .into_iter()
+            .map(|entry| {
+                let mv = Move {
+                    player: player.clone(),
+                    from: entry.from.clone(),
+                    to: entry.to.clone(),
+                };
+                (mv, entry.weight())
+            })
+            .filter(|(mv, _)| legal.contains(mv))
+            .collect();
+
+        let total: u32 = moves.iter().map(|(_, weight)| weight).sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut pick = rand::thread_rng().gen_range(0, total);
+        for (mv, weight) in moves {
+            if pick < weight {
+                return Some(mv);
+            }
+            pick -= weight;
+        }
+
+        unreachable!("pick is below the total weight")
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut data = MAGIC.to_vec();
+        data.push(self.variant.len() as u8);
+        data.extend_from_slice(self.variant.as_bytes());
+        data.extend_from_slice(&[self.width, self.height]);
+        data.extend_from_slice(&(self.entries.len() as u32).to_le_bytes());
+
+        for (key, entry) in &self.entries {
+            data.extend_from_slice(&key.to_le_bytes());
+            data.extend_from_slice(&[entry.from.x, entry.from.y, entry.to.x, entry.to.y]);
+            for count in &[entry.games, entry.wins, entry.draws, entry.losses] {
+                data.extend_from_slice(&count.to_le_bytes());
+            }
+        }
+
+        fs::write(path, data).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Book, String> {
+        let data = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        let corrupt = || format!("{}: not an opening book", path);
+
+        if data.len() < 5 || &data[..4] != MAGIC {
+            return Err(corrupt());
+        }
+        let name_end = 5 + usize::from(data[4]);
+        let header_end = name_end + 2 + 4;
+        if data.len() < header_end {
+            return Err(corrupt());
+        }
+
+        let variant = String::from_utf8(data[5..name_end].to_vec()).map_err(|_| corrupt())?;
+        let mut book = Book::new(&variant, data[name_end], data[name_end + 1]);
+        let mut count = [0; 4];
+        count.copy_from_slice(&data[name_end + 2..header_end]);
+        let count = u32::from_le_bytes(count) as usize;
+
+        let body = &data[header_end..];
+        if body.len() != count * ENTRY_SIZE {
+            return Err(corrupt());
+        }
+
+        let u32_at = |bytes: &[u8], at: usize| {
+            let mut word = [0; 4];
+            word.copy_from_slice(&bytes[at..at + 4]);
+            u32::from_le_bytes(word)
+        };
+
+        for bytes in body.chunks(ENTRY_SIZE) {
+            let mut key = [0; 8];
+            key.copy_from_slice(&bytes[..8]);
+
+            book.entries.push((
+                u64::from_le_bytes(key),
+                BookMove {
+                    from: Position {
+                        x: bytes[8],
+                        y: bytes[9],
+                    },
+                    to: Position {
+                        x: bytes[10],
+                        y: bytes[11],
+                    },
+                    games: u32_at(bytes, 12),
+                    wins: u32_at(bytes, 16),
+                    draws: u32_at(bytes, 20),
+                    losses: u32_at(bytes, 24),
+                },
+            ));
+        }
+
+        Ok(book)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::rules::Classic;
+
+    /// Book for the start of a classic game with every legal first move of
+    /// White, the first one won and the second drawn.
+    fn opening() -> (GameState<Classic>, Book) {
+        let state = GameState::<Classic>::new(8, 8);
+        let mut book = Book::new(Classic::NAME, 8, 8);
+        for (i, mv) in state.legal_moves_for(&Player::White).iter().enumerate() {
+            let winner = match i {
+                0 => Some(&Player::White),
+                1 => None,
+                _ => Some(&Player::Black),
+            };
+            book.add(&state, mv, winner);
+        }
+
+        (state, book)
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let (state, book) = opening();
+        let path = env::temp_dir().join(format!("horse-book-{}.hbk", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        book.save(path).unwrap();
+        let loaded = Book::load(path);
+        fs::remove_file(path).unwrap();
+        let loaded = loaded.unwrap();
+
+        assert_eq!(loaded.variant, book.variant);
+        assert_eq!((loaded.width, loaded.height), (8, 8));
+        assert_eq!(loaded.entries, book.entries);
+        assert_eq!(loaded.moves(&state), book.moves(&state));
+        assert_eq!(loaded.positions(), 1);
+    }
+
+    #[test]
+    fn load_rejects_other_files() {
+        let path = env::temp_dir().join(format!("horse-not-book-{}.hbk", std::process::id()));
+        let path = path.to_str().unwrap();
+
+        fs::write(path, b"HBK1\x07classic").unwrap();
+        let loaded = Book::load(path);
+        fs::remove_file(path).unwrap();
+
+        assert!(loaded.is_err());
+    }
+
+    #[test]
+    fn pick_only_returns_legal_moves() {
+        let (state, mut book) = opening();
+        let legal = state.legal_moves_for(&Player::White);
+        // A knight staying put, winning every game it was played in.
+        let stay = Move {
+            player: Player::White,
+            from: legal[0].from.clone(),
+            to: legal[0].from.clone(),
+        };
+        for _ in 0..100 {
+            book.add(&state, &stay, Some(&Player::White));
+        }
+
+        for _ in 0..200 {
+            let mv = book.pick(&state, &Player::White).unwrap();
+            assert!(legal.contains(&mv), "{:?} is not legal", mv);
+        }
+        assert_eq!(book.pick(&state, &Player::Black), None);
+    }
+
+    #[test]
+    fn pick_ignores_other_board_sizes() {
+        let (_, book) = opening();
+        let state = GameState::<Classic>::new(10, 10);
+
+        assert_eq!(book.pick(&state, &Player::White), None);
+    }
+}
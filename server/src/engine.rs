@@ -0,0 +1,689 @@
+//! Built-in computer player: alpha-beta search over `GameState`.
+//!
+//! Games with more than two sides are searched "paranoid": the engine's team
+//! plays against everyone else, so plain negamax applies. Consecutive moves of
+//! the same side (extra moves, partners) don't flip the score.
+
+use std::fmt;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crossbeam::channel::{bounded, Receiver};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::book::Book;
+use crate::evaluation::Weights;
+use crate::gamesate::GameState;
+use crate::rules::Ruleset;
+use crate::tablebase::{Tablebase, Value};
+use crate::tt::{Bound, Entry, TranspositionTable};
+use crate::types::*;
+
+/// Score of a won game, shortened by the number of plies to reach it.
+const WIN: i32 = 1_000_000;
+/// Usual value of a piece on the board, scaling the margins below.
+const PIECE_VALUE: i32 = 100;
+/// Half-width of the aspiration window around the previous iteration's value.
+const ASPIRATION: i32 = PIECE_VALUE / 2;
+/// The transposition table has 2^TT_BITS entries.
+const TT_BITS: u32 = 18;
+/// Pondering has no deadline of its own, it runs until stopped.
+const PONDER_LIMIT: Duration = Duration::from_secs(3600);
+
+/// Search limits of the engine.
+#[derive(Debug, Clone)]
+pub struct Engine {
+    /// Deepest iteration of iterative deepening, in plies.
+    pub max_depth: u8,
+    /// Time after which the running iteration is abandoned.
+    pub time_limit: Duration,
+    /// Largest random bonus given to each move, so weaker levels
+    /// sometimes prefer a worse move.
+    pub randomness: i32,
+    /// Search threads, sharing the transposition table.
+    pub threads: usize,
+    /// Endgame tablebase giving exact values of positions it covers.
+    pub tablebase: Option<Arc<Tablebase>>,
+    /// Opening book played from while it has moves for the position.
+    pub book: Option<Arc<Book>>,
+    /// Weights of the static evaluation.
+    pub weights: Weights,
+}
+
+impl Default for Engine {
+    fn default() -> Engine {
+        Difficulty::Hard.engine()
+    }
+}
+
+/// Strength of the engine.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Difficulty {
+    Beginner,
+    Easy,
+    Medium,
+    Hard,
+}
+
+pub const DIFFICULTY_HELP: &str = "beginner, easy, medium, hard";
+
+impl Difficulty {
+    /// Engine limited to this level.
+    pub fn engine(&self) -> Engine {
+        let (max_depth, time_limit, randomness) = match self {
+            Difficulty::Beginner => (1, 100, 2 * PIECE_VALUE),
+            Difficulty::Easy => (2, 250, PIECE_VALUE / 2),
+            Difficulty::Medium => (3, 500, PIECE_VALUE / 5),
+            Difficulty::Hard => (32, 1000, 0),
+        };
+
+        Engine {
+            max_depth,
+            time_limit: Duration::from_millis(time_limit),
+            randomness,
+            threads: 1,
+            tablebase: None,
+            book: None,
+            weights: Weights::default(),
+        }
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Difficulty, String> {
+        match s {
+            "beginner" => Ok(Difficulty::Beginner),
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!(
+                "Unknown difficulty '{}', expected one of: {}",
+                s, DIFFICULTY_HELP
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Difficulty::Beginner => "beginner",
+            Difficulty::Easy => "easy",
+            Difficulty::Medium => "medium",
+            Difficulty::Hard => "hard",
+        };
+
+        write!(f, "{}", name)
+    }
+}
+
+/// Outcome of a search.
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub best: Option<Move>,
+    /// Expected reply to `best`, taken from the transposition table.
+    pub ponder: Option<Move>,
+    /// Value of the position for the player found by the last completed
+    /// iteration, `None` for book and tablebase moves.
+    pub value: Option<i32>,
+}
+
+/// Search running in the background on the position expected after the
+/// opponent's reply. Dropping it stops the search.
+pub struct Ponder {
+    hash: u64,
+    stop: Arc<AtomicBool>,
+    result: Receiver<SearchResult>,
+}
+
+impl Ponder {
+    /// Whether the pondered position is `state`.
+    pub fn hit<R: Ruleset>(&self, state: &GameState<R>) -> bool {
+        self.hash == state.hash()
+    }
+
+    /// Lets the search go on until `deadline` and returns its result.
+    pub fn finish(&self, deadline: Instant) -> Option<SearchResult> {
+        let timeout = deadline.saturating_duration_since(Instant::now());
+
+        self.result.recv_timeout(timeout).ok().or_else(|| {
+            self.stop.store(true, Ordering::Relaxed);
+            self.result.recv().ok()
+        })
+    }
+}
+
+impl Drop for Ponder {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// State of a single search thread.
+struct Search<'a> {
+    /// Player the engine moves for.
+    root: Player,
+    deadline: Instant,
+    /// Set by the main thread when it's done, helpers stop then.
+    stop: &'a AtomicBool,
+    /// Set once the deadline passed, results of the running iteration are void.
+    aborted: bool,
+    /// Shared by all threads of the search.
+    tt: &'a TranspositionTable,
+    tablebase: Option<&'a Tablebase>,
+    weights: &'a Weights,
+    /// Two latest quiet moves causing a cut-off, by ply.
+    killers: Vec<[Option<Move>; 2]>,
+    /// Cut-offs caused by quiet moves, weighted by depth, by from and to square.
+    history: Vec<u32>,
+    /// Squares on the board, `history` has one row of them per square.
+    squares: usize,
+    width: usize,
+}
+
+impl Engine {
+    /// Move with the best tablebase value if the tablebase covers `state`:
+    /// the fastest win, otherwise a draw, otherwise the slowest loss.
+    fn tablebase_move<R: Ruleset>(&self, state: &GameState<R>, player: &Player) -> Option<Move> {
+        let tablebase = self.tablebase.as_ref()?;
+        tablebase.probe_state(state)?;
+
+        state.legal_moves_for(player).into_iter().max_by_key(|mv| {
+            let mut child = state.clone();
+            child.make_move(mv.clone());
+
+            match tablebase.probe_state(&child) {
+                // Captures lead to smaller tables, so the move ended the game.
+                None => WIN,
+                Some(Value::Loss(distance)) => WIN - i32::from(distance),
+                Some(Value::Draw) => 0,
+                Some(Value::Win(distance)) => i32::from(distance) - WIN,
+            }
+        })
+    }
+
+    /// Best move for `player` found within the limits, `None` if it's not their turn.
+    pub fn best_move<R: Ruleset>(&self, state: &GameState<R>, player: &Player) -> Option<Move> {
+        let deadline = Instant::now() + self.time_limit;
+        self.search(state, player, deadline, &AtomicBool::new(false))
+            .best
+    }
+
+    /// Starts searching `state`, where `player` is expected to move next,
+    /// in the background until the returned `Ponder` is finished or dropped.
+    pub fn ponder<R: Ruleset>(&self, state: GameState<R>, player: Player) -> Ponder {
+        let stop = Arc::new(AtomicBool::new(false));
+        let (result_writer, result) = bounded(1);
+        let hash = state.hash();
+        let engine = self.clone();
+        let thread_stop = stop.clone();
+
+        thread::spawn(move || {
+            let deadline = Instant::now() + PONDER_LIMIT;
+            let _ = result_writer.send(engine.search(&state, &player, deadline, &thread_stop));
+        });
+
+        Ponder { hash, stop, result }
+    }
+
+    /// Best move for `player` (`None` if it's not their turn) and the expected
+    /// reply, searching until `deadline` or until `stop` is set.
+    ///
+    /// With several threads the search is Lazy SMP: helper threads run the same
+    /// iterative deepening with root moves in a different order, their results
+    /// reach the main thread only through the shared transposition table.
+    pub fn search<R: Ruleset>(
+        &self,
+        state: &GameState<R>,
+        player: &Player,
+        deadline: Instant,
+        stop: &AtomicBool,
+    ) -> SearchResult {
+        if state.to_move().as_ref() != Some(player) {
+            return SearchResult {
+                best: None,
+                ponder: None,
+                value: None,
+            };
+        }
+
+        let book_move = self.book.as_ref().and_then(|book| book.pick(state, player));
+        if let Some(best) = book_move.or_else(|| self.tablebase_move(state, player)) {
+            return SearchResult {
+                best: Some(best),
+                ponder: None,
+                value: None,
+            };
+        }
+
+        // Every move keeps its random bonus through all iterations.
+        let mut rng = rand::thread_rng();
+        let moves: Vec<(Move, i32)> = state
+            .legal_moves_for(player)
+            .into_iter()
+            .map(|mv| (mv, rng.gen_range(0, self.randomness + 1)))
+            .collect();
+        let tt = TranspositionTable::new(TT_BITS);
+        let tt = &tt;
+        let tablebase = self.tablebase.as_deref();
+        let weights = &self.weights;
+
+        let (best, value) = crossbeam::scope(|scope| {
+            for helper in 1..self.threads.max(1) {
+                let mut moves = moves.clone();
+                if !moves.is_empty() {
+                    let shift = helper % moves.len();
+                    moves.rotate_left(shift);
+                }
+
+                scope.spawn(move |_| {
+                    let mut search =
+                        Search::new(state, player, deadline, stop, tt, tablebase, weights);
+                    self.iterate(&mut search, state, moves);
+                });
+            }
+
+            let mut search = Search::new(state, player, deadline, stop, tt, tablebase, weights);
+            let result = self.iterate(&mut search, state, moves);
+            stop.store(true, Ordering::Relaxed);
+            result
+        })
+        .expect("search thread panicked");
+
+        let ponder = best.as_ref().and_then(|mv| {
+            let mut child = state.clone();
+            child.make_move(mv.clone());
+            let mover = child.to_move()?;
+            let (from, to) = tt.probe(child.hash())?.best?;
+            let reply = Move {
+                player: mover.clone(),
+                from,
+                to,
+            };
+
+            if child.legal_moves_for(&mover).contains(&reply) {
+                Some(reply)
+            } else {
+                None
+            }
+        });
+
+        SearchResult {
+            best,
+            ponder,
+            value,
+        }
+    }
+
+    /// Iterative deepening over root `moves`, best move and value of the
+    /// last completed iteration.
+    fn iterate<R: Ruleset>(
+        &self,
+        search: &mut Search,
+        state: &GameState<R>,
+        mut moves: Vec<(Move, i32)>,
+    ) -> (Option<Move>, Option<i32>) {
+        let mut best = moves.first().map(|(mv, _)| mv.clone());
+        let mut previous = None;
+
+        for depth in 1..=self.max_depth {
+            // Aspiration window around the previous iteration's value,
+            // widened to the full range if the value falls outside.
+            let (mut alpha, mut beta) = match previous {
+                Some(value) => (value - ASPIRATION, value + ASPIRATION),
+                None => (-WIN - 1, WIN + 1),
+            };
+
+            let (value, best_in_iteration) = loop {
+                let (value, best) = search.root(state, &moves, depth, alpha, beta);
+
+                if search.aborted {
+                    break (value, None);
+                } else if value <= alpha && alpha > -WIN - 1 {
+                    alpha = -WIN - 1;
+                } else if value >= beta && beta < WIN + 1 {
+                    beta = WIN + 1;
+                } else {
+                    break (value, best);
+                }
+            };
+
+            if search.aborted {
+                break;
+            }
+
+            if let Some((mv, bonus)) = best_in_iteration {
+                // Searching the best move first makes the next iteration cut more.
+                moves.retain(|(other, _)| *other != mv);
+                moves.insert(0, (mv.clone(), bonus));
+                best = Some(mv);
+            }
+            previous = Some(value);
+
+            // Forced win or loss found, deeper search won't change it.
+            if value.abs() > WIN - i32::from(self.max_depth) - self.randomness {
+                break;
+            }
+        }
+
+        (best, previous)
+    }
+}
+
+/// Values this close to `WIN` are wins or losses found by the search.
+fn is_decisive(value: i32) -> bool {
+    value.abs() > WIN - 1000
+}
+
+/// Decisive values are stored as distance from the stored position, not from the root.
+fn value_to_tt(value: i32, ply: i32) -> i32 {
+    match value {
+        value if is_decisive(value) && value > 0 => value + ply,
+        value if is_decisive(value) => value - ply,
+        value => value,
+    }
+}
+
+fn value_from_tt(value: i32, ply: i32) -> i32 {
+    match value {
+        value if is_decisive(value) && value > 0 => value - ply,
+        value if is_decisive(value) => value + ply,
+        value => value,
+    }
+}
+
+impl<'a> Search<'a> {
+    fn new<R: Ruleset>(
+        state: &GameState<R>,
+        player: &Player,
+        deadline: Instant,
+        stop: &'a AtomicBool,
+        tt: &'a TranspositionTable,
+        tablebase: Option<&'a Tablebase>,
+        weights: &'a Weights,
+    ) -> Search<'a> {
+        let (width, height) = state.size();
+        let squares = width as usize * height as usize;
+
+        Search {
+            root: player.clone(),
+            deadline,
+            stop,
+            aborted: false,
+            tt,
+            tablebase,
+            weights,
+            killers: vec![],
+            history: vec![0; squares * squares],
+            squares,
+            width: width as usize,
+        }
+    }
+
+    /// Whether `player` plays on the engine's side.
+    fn is_root_side<R: Ruleset>(&self, state: &GameState<R>, player: &Player) -> bool {
+        state.allies(&self.root, player)
+    }
+
+    /// Searches root moves (with their random bonuses) in order,
+    /// returns the best value and move.
+    fn root<R: Ruleset>(
+        &mut self,
+        state: &GameState<R>,
+        moves: &[(Move, i32)],
+        depth: u8,
+        mut alpha: i32,
+        beta: i32,
+    ) -> (i32, Option<(Move, i32)>) {
+        let mut best_value = -WIN - 1;
+        let mut best = None;
+
+        for (mv, bonus) in moves {
+            let mut child = state.clone();
+            child.make_move(mv.clone());
+            let value =
+                bonus + self.child_value(&child, true, depth - 1, 1, alpha - bonus, beta - bonus);
+
+            if self.aborted {
+                break;
+            }
+
+            if value > best_value {
+                best_value = value;
+                best = Some((mv.clone(), *bonus));
+            }
+
+            alpha = alpha.max(value);
+
+            if alpha >= beta {
+                break;
+            }
+        }
+
+        (best_value, best)
+    }
+
+    /// Value of `child` reached by a move of `parent_side`,
+    /// from the point of view of `parent_side` (true for the engine's side).
+    fn child_value<R: Ruleset>(
+        &mut self,
+        child: &GameState<R>,
+        parent_side: bool,
+        depth: u8,
+        ply: i32,
+        alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        let side = match child.to_move() {
+            Some(player) => self.is_root_side(child, &player),
+            None => parent_side,
+        };
+
+        if side == parent_side {
+            self.negamax(child, side, depth, ply, alpha, beta)
+        } else {
+            -self.negamax(child, side, depth, ply, -beta, -alpha)
+        }
+    }
+
+    fn history_index(&self, mv: &Move) -> usize {
+        let from = mv.from.y as usize * self.width + mv.from.x as usize;
+        let to = mv.to.y as usize * self.width + mv.to.x as usize;
+        from * self.squares + to
+    }
+
+    /// Orders moves: transposition table move, captures, killers,
+    /// then quiet moves by history.
+    fn order_moves<R: Ruleset>(
+        &self,
+        state: &GameState<R>,
+        moves: &mut [Move],
+        tt_move: Option<&Move>,
+        ply: usize,
+    ) {
+        let killers = self.killers.get(ply);
+
+        moves.sort_by_cached_key(|mv| {
+            let score = if Some(mv) == tt_move {
+                u32::MAX
+            } else if state.is_capture(mv) {
+                u32::MAX - 1
+            } else if killers.is_some_and(|killers| killers[0].as_ref() == Some(mv)) {
+                u32::MAX - 2
+            } else if killers.is_some_and(|killers| killers[1].as_ref() == Some(mv)) {
+                u32::MAX - 3
+            } else {
+                self.history[self.history_index(mv)].min(u32::MAX - 4)
+            };
+
+            std::cmp::Reverse(score)
+        });
+    }
+
+    /// Remembers a quiet move causing a cut-off for ordering.
+    fn record_cutoff(&mut self, mv: &Move, depth: u8, ply: usize) {
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None, None]);
+        }
+
+        let killers = &mut self.killers[ply];
+        if killers[0].as_ref() != Some(mv) {
+            killers[1] = killers[0].take();
+            killers[0] = Some(mv.clone());
+        }
+
+        let index = self.history_index(mv);
+        self.history[index] =
+            self.history[index].saturating_add(u32::from(depth) * u32::from(depth));
+    }
+
+    /// Alpha-beta search, value from the point of view of `side`.
+    fn negamax<R: Ruleset>(
+        &mut self,
+        state: &GameState<R>,
+        side: bool,
+        depth: u8,
+        ply: i32,
+        mut alpha: i32,
+        beta: i32,
+    ) -> i32 {
+        if Instant::now() >= self.deadline || self.stop.load(Ordering::Relaxed) {
+            self.aborted = true;
+            return 0;
+        }
+
+        let mover = match state.to_move() {
+            Some(mover) if depth > 0 => mover,
+            _ => {
+                let value = self.evaluate(state, ply);
+                return if side { value } else { -value };
+            }
+        };
+
+        if let Some(value) = self.probe(state, &mover, ply) {
+            return if side { value } else { -value };
+        }
+
+        let key = state.hash();
+        let original_alpha = alpha;
+        let mut tt_move = None;
+
+        if let Some(entry) = self.tt.probe(key) {
+            tt_move = entry.best.map(|(from, to)| Move {
+                player: mover.clone(),
+                from,
+                to,
+            });
+
+            if entry.depth >= depth {
+                let value = value_from_tt(entry.value, ply);
+
+                match entry.bound {
+                    Bound::Exact => return value,
+                    Bound::Lower if value >= beta => return value,
+                    Bound::Upper if value <= alpha => return value,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut moves = state.legal_moves_for(&mover);
+        self.order_moves(state, &mut moves, tt_move.as_ref(), ply as usize);
+
+        let mut best = -WIN - 1;
+        let mut best_move = None;
+
+        for mv in moves {
+            let mut child = state.clone();
+            child.make_move(mv.clone());
+            let value = self.child_value(&child, side, depth - 1, ply + 1, alpha, beta);
+
+            if self.aborted {
+                return 0;
+            }
+
+            if value > best {
+                best = value;
+                best_move = Some(mv.clone());
+            }
+
+            alpha = alpha.max(value);
+
+            if alpha >= beta {
+                if !state.is_capture(&mv) {
+                    self.record_cutoff(&mv, depth, ply as usize);
+                }
+                break;
+            }
+        }
+
+        let bound = if best <= original_alpha {
+            Bound::Upper
+        } else if best >= beta {
+            Bound::Lower
+        } else {
+            Bound::Exact
+        };
+
+        self.tt.store(Entry {
+            key,
+            depth,
+            value: value_to_tt(best, ply),
+            bound,
+            best: best_move.map(|mv| (mv.from, mv.to)),
+        });
+
+        best
+    }
+    /// Exact value of a position the tablebase covers, for the engine's side.
+    fn probe<R: Ruleset>(&self, state: &GameState<R>, mover: &Player, ply: i32) -> Option<i32> {
+        let value = match self.tablebase?.probe_state(state)? {
+            Value::Win(distance) => WIN - ply - i32::from(distance),
+            Value::Loss(distance) => ply + i32::from(distance) - WIN,
+            Value::Draw => 0,
+        };
+
+        Some(if self.is_root_side(state, mover) {
+            value
+        } else {
+            -value
+        })
+    }
+
+    /// Static value of the position for the engine's side: the weighted
+    /// features of its team minus those of everyone else.
+    fn evaluate<R: Ruleset>(&self, state: &GameState<R>, ply: i32) -> i32 {
+        if state.is_finished() {
+            return match state.get_winner() {
+                Some(winner) if self.is_root_side(state, &winner) => WIN - ply,
+                Some(_) => ply - WIN,
+                None => 0,
+            };
+        }
+
+        let mut value = 0;
+
+        for player in state.players() {
+            if state.is_out(player) {
+                continue;
+            }
+
+            let side = self.weights.evaluate(state, player);
+
+            if self.is_root_side(state, player) {
+                value += side;
+            } else {
+                value -= side;
+            }
+        }
+
+        value
+    }
+}
@@ -0,0 +1,93 @@
+//! Static evaluation of the alpha-beta engine: a weighted sum of features
+//! of each side's pieces. Weights are hand-picked by default, or tuned on
+//! archived games by `horse-tune` and loaded from a JSON file.
+
+use std::fs;
+
+use serde::{Deserialize, Serialize};
+
+use crate::gamesate::GameState;
+use crate::rules::Ruleset;
+use crate::types::*;
+
+/// Value of each unit of a feature.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Weights {
+    /// Per piece on the board.
+    pub material: i32,
+    /// Per move the pieces could make.
+    pub mobility: i32,
+    /// Per square between a piece and the nearest edge.
+    pub centralization: i32,
+    /// Per piece another piece of the same player could recapture on.
+    pub clusters: i32,
+}
+
+impl Default for Weights {
+    fn default() -> Weights {
+        Weights {
+            material: 100,
+            mobility: 4,
+            centralization: 0,
+            clusters: 0,
+        }
+    }
+}
+
+impl Weights {
+    pub fn load(path: &str) -> Result<Weights, String> {
+        let data = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        serde_json::from_str(&data).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut data = serde_json::to_string_pretty(self).expect("Serialization errror");
+        data.push('\n');
+        fs::write(path, data).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    /// Value of `player`'s side in `state`, features weighted 0 aren't
+    /// computed.
+    pub fn evaluate<R: Ruleset>(&self, state: &GameState<R>, player: &Player) -> i32 {
+        let mut value = self.material * i32::from(state.count_pieces(player));
+        if self.mobility != 0 {
+            value += self.mobility * state.mobility(player) as i32;
+        }
+        if self.centralization != 0 {
+            value += self.centralization * state.centralization(player) as i32;
+        }
+        if self.clusters != 0 {
+            value += self.clusters * state.clusters(player) as i32;
+        }
+
+        value
+    }
+
+    /// Value of a side with `features`.
+    pub fn value(&self, features: &Features) -> i32 {
+        self.material * features.material
+            + self.mobility * features.mobility
+            + self.centralization * features.centralization
+            + self.clusters * features.clusters
+    }
+}
+
+/// What the evaluation counts for one player, see `Weights`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Features {
+    pub material: i32,
+    pub mobility: i32,
+    pub centralization: i32,
+    pub clusters: i32,
+}
+
+impl Features {
+    pub fn of<R: Ruleset>(state: &GameState<R>, player: &Player) -> Features {
+        Features {
+            material: i32::from(state.count_pieces(player)),
+            mobility: state.mobility(player) as i32,
+            centralization: state.centralization(player) as i32,
+            clusters: state.clusters(player) as i32,
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Bots running as separate programs, talking a line based protocol
+//! over their stdin and stdout.
+//!
+//! Server to bot:
+//!
+//! - `horse`: sent once at start, the bot answers with optional
+//!   `id name NAME` and `id author AUTHOR` lines followed by `horseok`.
+//! - `player PLAYER`: sent after the handshake, the seat the bot plays,
+//!   e.g. `player white`.
+//! - `position JSON`: the bot's view of the game, as sent to clients.
+//! - `go movetime MS`: the bot should answer with `bestmove` within MS
+//!   milliseconds.
+//! - `stop`: the time is up, answer with `bestmove` right away.
+//! - `quit`: the game is over, the bot should exit (also when its stdin
+//!   is closed).
+//!
+//! Bot to server:
+//!
+//! - `info TEXT`: anything the bot wants to report, printed by the server.
+//! - `bestmove FROM TO`: the move found, squares in the client's notation,
+//!   e.g. `bestmove b7 c5`.
+//!
+//! Other lines are ignored.
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam::channel::{unbounded, Receiver, RecvTimeoutError};
+
+use crate::gamesate::GameState;
+use crate::rules::Ruleset;
+use crate::types::*;
+
+/// Time a bot has to answer the handshake.
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Time a bot has to answer `stop`, after its move time is over.
+const STOP_TIMEOUT: Duration = Duration::from_secs(1);
+/// Time a bot has to exit after `quit` before it's killed.
+const QUIT_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// Running bot program.
+pub struct External {
+    /// Name the bot gave in the handshake, the command otherwise.
+    pub name: String,
+    child: Child,
+    input: ChildStdin,
+    /// Lines of the bot's output, read in another thread.
+    output: Receiver<String>,
+}
+
+impl External {
+    /// Starts `command` (program followed by arguments, separated by spaces)
+    /// to play `player` and waits for the handshake.
+    pub fn spawn(command: &str, player: &Player) -> Result<External, String> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or("Empty bot command")?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Unable to start '{}': {}", command, e))?;
+
+        let input = child.stdin.take().expect("stdin is piped");
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let (lines, output) = unbounded();
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                match line.map(|line| lines.send(line)) {
+                    Ok(Ok(())) => {}
+                    _ => return,
+                }
+            }
+        });
+
+        let mut bot = External {
+            name: command.to_string(),
+            child,
+            input,
+            output,
+        };
+
+        bot.send("horse")?;
+        loop {
+            let line = bot.receive(HANDSHAKE_TIMEOUT)?;
+            let mut words = line.split_whitespace();
+
+            match (words.next(), words.next()) {
+                (Some("horseok"), _) => break,
+                (Some("id"), Some("name")) => bot.name = words.collect::<Vec<_>>().join(" "),
+                _ => {}
+            }
+        }
+        bot.send(&format!(
+            "player {}",
+            format!("{:?}", player).to_lowercase()
+        ))?;
+
+        Ok(bot)
+    }
+
+    /// Asks the bot for `player`'s move in `state`, giving it `movetime` to think.
+    pub fn best_move<R: Ruleset>(
+        &mut self,
+        state: &GameState<R>,
+        player: &Player,
+        movetime: Duration,
+    ) -> Result<Move, String> {
+        let position = serde_json::to_string(state).expect("Serialization errror");
+        self.send(&format!("position {}", position))?;
+        self.send(&format!("go movetime {}", movetime.as_millis()))?;
+
+        let mut timeout = movetime;
+        let mut stopped = false;
+        loop {
+            let line = match self.receive(timeout) {
+                Ok(line) => line,
+                Err(_) if !stopped => {
+                    self.send("stop")?;
+                    stopped = true;
+                    timeout = STOP_TIMEOUT;
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            let mut words = line.split_whitespace();
+
+            match words.next() {
+                Some("info") => println!("{}: {}", self.name, line),
+                Some("bestmove") => {
+                    let mv = match (words.next(), words.next()) {
+                        (Some(from), Some(to)) => Move {
+                            player: player.clone(),
+                            from: from.parse()?,
+                            to: to.parse()?,
+                        },
+                        _ => return Err(format!("{}: malformed '{}'", self.name, line)),
+                    };
+
+                    if !state.legal_moves_for(player).contains(&mv) {
+                        return Err(format!("{}: illegal move {} {}", self.name, mv.from, mv.to));
+                    }
+                    return Ok(mv);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), String> {
+        writeln!(self.input, "{}", line)
+            .and_then(|_| self.input.flush())
+            .map_err(|e| format!("{}: {}", self.name, e))
+    }
+
+    fn receive(&self, timeout: Duration) -> Result<String, String> {
+        self.output.recv_timeout(timeout).map_err(|e| match e {
+            RecvTimeoutError::Timeout => format!("{}: no answer in time", self.name),
+            RecvTimeoutError::Disconnected => format!("{}: exited", self.name),
+        })
+    }
+}
+
+impl Drop for External {
+    fn drop(&mut self) {
+        let _ = self.send("quit");
+        thread::sleep(QUIT_TIMEOUT);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -1,96 +1,772 @@
+use std::marker::PhantomData;
+
 use serde::{Deserialize, Serialize};
 
+use crate::book::BookMove;
+use crate::handicap::Handicap;
+use crate::rules::Ruleset;
+use crate::tablebase::Knights;
 use crate::types::*;
+use crate::victory::Victory;
+use crate::zobrist;
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
-pub struct GameState {
+pub struct GameState<R: Ruleset> {
+    variant: String,
+    width: u8,
+    height: u8,
+    topology: Topology,
+    kinds: Vec<PieceKind>,
     board: Board,
+    /// Seats in turn order.
+    players: Vec<Player>,
+    /// Players winning together, every player is in exactly one team.
+    teams: Vec<Vec<Player>>,
     current_player: Player,
     move_history: History,
     finished: bool,
     winner: Option<Player>,
+    victory: Victory,
+    /// Captures made by each player, in `players` order.
+    captures: Vec<u8>,
+    /// Players left without moves, they are out of the game.
+    eliminated: Vec<Player>,
+    /// Odds given in this game.
+    handicaps: Vec<Handicap>,
+    /// Seats played by programs rather than people.
+    bots: Vec<Player>,
+    /// Opening book moves from the position, sent to clients for exploring.
+    explorer: Vec<BookMove>,
+    /// Hints each player may still ask for, in `players` order. Empty when
+    /// the game has no hints.
+    hints: Vec<u8>,
+    /// Move suggested to the player who asked for a hint, until a move is made.
+    /// Only their view shows it.
+    hint: Option<Move>,
+    /// Moves each player may still make in a row, in `players` order.
+    extra_moves: Vec<u8>,
+    /// Players see only squares their pieces occupy or attack.
+    fog: bool,
+    /// Players move at the same time, see `resolve_turn`.
+    simultaneous: bool,
+    /// Players who submitted their move this turn in simultaneous games.
+    ready: Vec<Player>,
+    /// Moves submitted this turn, kept secret until the turn is resolved.
+    #[serde(skip)]
+    pending: Vec<Move>,
+    #[serde(skip)]
+    rules: PhantomData<R>,
+}
+
+impl<R: Ruleset> Default for GameState<R> {
+    fn default() -> GameState<R> {
+        let (width, height) = R::default_size();
+        GameState::new(width, height)
+    }
 }
 
-impl Default for GameState {
-    fn default() -> GameState {
-        use crate::types::Piece::*;
-
-        let board = [
-            [Black, Empty, Black, Empty, Black, Empty, Black, Empty],
-            [Empty, Black, Empty, Black, Empty, Black, Empty, Black],
-            [Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty],
-            [Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty],
-            [Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty],
-            [Empty, Empty, Empty, Empty, Empty, Empty, Empty, Empty],
-            [White, Empty, White, Empty, White, Empty, White, Empty],
-            [Empty, White, Empty, White, Empty, White, Empty, White],
-        ];
+impl<R: Ruleset> GameState<R> {
+    /// Creates a game on `width` x `height` board with starting position of the variant.
+    pub fn new(width: u8, height: u8) -> GameState<R> {
+        let mut board = R::setup(width, height);
+
+        for pos in R::obstacles(width, height) {
+            board[pos.y as usize][pos.x as usize] = Piece::Blocked;
+        }
+
+        let players = R::players();
+
         GameState {
+            variant: R::NAME.to_string(),
+            width,
+            height,
+            topology: R::topology(),
+            kinds: R::kinds(),
             board,
-            current_player: Player::White,
+            current_player: players[0].clone(),
+            captures: vec![0; players.len()],
+            extra_moves: vec![0; players.len()],
+            handicaps: vec![],
+            bots: vec![],
+            explorer: vec![],
+            hints: vec![],
+            hint: None,
+            players,
+            teams: R::teams(),
+            eliminated: vec![],
             move_history: vec![],
             finished: false,
             winner: None,
+            victory: R::default_victory(),
+            fog: R::fog(),
+            simultaneous: R::simultaneous(),
+            ready: vec![],
+            pending: vec![],
+            rules: PhantomData,
+        }
+    }
+
+    /// Replaces the variant's default victory condition.
+    pub fn with_victory(mut self, victory: Victory) -> GameState<R> {
+        self.victory = victory;
+        self
+    }
+
+    /// Checks the game isn't decided before anyone moves: no player is
+    /// already out or has already won, and the first player can move.
+    pub fn check_start(&self) -> Result<(), String> {
+        if let Some(player) = self.players.iter().find(|player| self.is_out(player)) {
+            return Err(format!(
+                "{:?} is out of the game from the start under victory '{}'",
+                player, self.victory
+            ));
+        }
+
+        if let Some(player) = self.victory_winner() {
+            return Err(format!(
+                "{:?} has won from the start under victory '{}'",
+                player, self.victory
+            ));
+        }
+
+        if self.legal_moves_for(&self.current_player).is_empty() {
+            return Err(format!(
+                "{:?} can't move from the start",
+                self.current_player
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Lets every player ask for `count` hints.
+    pub fn with_hints(mut self, count: u8) -> GameState<R> {
+        self.hints = vec![count; self.players.len()];
+        self
+    }
+
+    /// Blocks given empty squares.
+    pub fn with_obstacles(mut self, obstacles: &[Position]) -> Result<GameState<R>, String> {
+        for pos in obstacles {
+            if !pos.valid(self.width, self.height) {
+                return Err(format!("Obstacle {} is outside of the board", pos));
+            }
+
+            let square = &mut self.board[pos.y as usize][pos.x as usize];
+
+            if square.owner().is_some() {
+                return Err(format!("Obstacle {} is occupied by a piece", pos));
+            }
+
+            *square = Piece::Blocked;
+        }
+
+        Ok(self)
+    }
+
+    /// Applies odds: removes knights farthest from the center first,
+    /// or lets the player move several times on their first turn.
+    pub fn with_handicap(mut self, handicap: Handicap) -> Result<GameState<R>, String> {
+        let player = match &handicap {
+            Handicap::Knights(player, _) | Handicap::ExtraMoves(player, _) => player,
+        };
+
+        if !self.players.contains(player) {
+            return Err(format!("{:?} doesn't play {}", player, self.variant));
+        }
+
+        match &handicap {
+            Handicap::Knights(player, count) => {
+                let knight = PieceKind::knight();
+                let mut knights = vec![];
+
+                for (y, row) in self.board.iter().enumerate() {
+                    for (x, piece) in row.iter().enumerate() {
+                        match piece {
+                            Piece::Leaper { kind, owner }
+                                if owner == player && self.kinds[*kind as usize] == knight =>
+                            {
+                                knights.push(Position {
+                                    x: x as u8,
+                                    y: y as u8,
+                                })
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+
+                if knights.len() < *count as usize {
+                    return Err(format!("{:?} has only {} knights", player, knights.len()));
+                }
+
+                // Squared distance from the center, in half-squares.
+                let (width, height) = (i32::from(self.width), i32::from(self.height));
+                knights.sort_by_key(|pos| {
+                    let dx = 2 * i32::from(pos.x) - (width - 1);
+                    let dy = 2 * i32::from(pos.y) - (height - 1);
+                    -(dx * dx + dy * dy)
+                });
+
+                for pos in knights.iter().take(*count as usize) {
+                    self.board[pos.y as usize][pos.x as usize] = Piece::Empty;
+                }
+
+                // Too few knights left for the victory condition.
+                self.check_start()?;
+            }
+            Handicap::ExtraMoves(player, count) => {
+                if self.simultaneous {
+                    return Err(String::from(
+                        "Extra moves can't be given in simultaneous games",
+                    ));
+                }
+
+                let seat = self.seat(player);
+                self.extra_moves[seat] += count;
+            }
+        }
+
+        self.handicaps.push(handicap);
+
+        Ok(self)
+    }
+
+    /// New game of this variant with the same board size, victory
+    /// condition, obstacles and handicaps, e.g. to replay a saved game.
+    pub fn restart(&self) -> Result<GameState<R>, String> {
+        if self.variant != R::NAME {
+            return Err(format!("{} is not a game of {}", self.variant, R::NAME));
+        }
+
+        let mut game_state =
+            GameState::<R>::new(self.width, self.height).with_victory(self.victory.clone());
+
+        let mut obstacles = vec![];
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, piece) in row.iter().enumerate() {
+                if *piece == Piece::Blocked && game_state.board[y][x] != Piece::Blocked {
+                    obstacles.push(Position {
+                        x: x as u8,
+                        y: y as u8,
+                    });
+                }
+            }
+        }
+        game_state = game_state.with_obstacles(&obstacles)?;
+
+        for handicap in &self.handicaps {
+            game_state = game_state.with_handicap(handicap.clone())?;
+        }
+
+        Ok(game_state)
+    }
+
+    pub fn get_handicaps(&self) -> &[Handicap] {
+        &self.handicaps
+    }
+
+    /// Hints `player` may still ask for.
+    pub fn hints_left(&self, player: &Player) -> u8 {
+        self.hints.get(self.seat(player)).copied().unwrap_or(0)
+    }
+
+    /// Suggests `mv` to the player making it, using up one of their hints.
+    pub fn give_hint(&mut self, mv: Move) {
+        let seat = self.seat(&mv.player);
+        self.hints[seat] -= 1;
+        self.hint = Some(mv);
+    }
+
+    /// Marks `player`'s seat as played by a program.
+    pub fn set_bot(&mut self, player: &Player) {
+        if !self.bots.contains(player) {
+            self.bots.push(player.clone());
+        }
+    }
+
+    /// Board width and height.
+    pub fn size(&self) -> (u8, u8) {
+        (self.width, self.height)
+    }
+
+    pub fn get_victory(&self) -> &Victory {
+        &self.victory
+    }
+
+    /// Seats in turn order.
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    /// Index of `player` in turn order.
+    fn seat(&self, player: &Player) -> usize {
+        self.players
+            .iter()
+            .position(|seated| seated == player)
+            .expect("player is not seated in this game")
+    }
+
+    fn team(&self, player: &Player) -> &[Player] {
+        self.teams
+            .iter()
+            .find(|team| team.contains(player))
+            .expect("player has no team in this game")
+    }
+
+    /// Whether players win together.
+    pub fn allies(&self, first: &Player, second: &Player) -> bool {
+        self.team(first).contains(second)
+    }
+
+    /// Checks move against the board and the variant rules,
+    /// ignoring compulsory captures.
+    fn is_pseudo_legal(&self, mv: &Move) -> bool {
+        self.is_to_move(&mv.player) && self.is_reachable(mv)
+    }
+
+    /// Whether the piece can make the move on the current board, whoever's turn it is.
+    fn is_reachable(&self, mv: &Move) -> bool {
+        if !mv.from.valid(self.width, self.height) {
+            return false;
+        }
+
+        let from_piece = &self.board[mv.from.y as usize][mv.from.x as usize];
+
+        let kind = match from_piece {
+            Piece::Leaper { kind, owner } if *owner == mv.player => &self.kinds[*kind as usize],
+            _ => return false,
+        };
+
+        if !mv.valid(self.width, self.height, &self.topology, &kind.leaps) {
+            return false;
+        }
+
+        let to_piece = &self.board[mv.to.y as usize][mv.to.x as usize];
+
+        if let Some(owner) = to_piece.owner() {
+            if self.allies(&owner, &mv.player) {
+                return false;
+            }
+        }
+
+        *to_piece != Piece::Blocked && R::can_capture(from_piece, to_piece)
+    }
+
+    pub fn is_capture(&self, mv: &Move) -> bool {
+        self.board[mv.to.y as usize][mv.to.x as usize]
+            .owner()
+            .is_some()
+    }
+
+    /// Whether `player` is expected to make a move now.
+    pub fn is_to_move(&self, player: &Player) -> bool {
+        if self.is_out(player) {
+            false
+        } else if self.simultaneous {
+            !self.ready.contains(player)
+        } else {
+            *player == self.current_player
+        }
+    }
+
+    /// Player whose move is awaited, in simultaneous games the first one not ready.
+    pub fn to_move(&self) -> Option<Player> {
+        if self.finished {
+            None
+        } else {
+            self.players
+                .iter()
+                .find(|player| self.is_to_move(player))
+                .cloned()
+        }
+    }
+
+    fn pseudo_legal_moves(&self, player: &Player) -> Vec<Move> {
+        if self.is_to_move(player) {
+            self.reachable_moves(player)
+        } else {
+            vec![]
+        }
+    }
+
+    /// Number of moves `player`'s pieces could make if it was their turn.
+    pub fn mobility(&self, player: &Player) -> usize {
+        self.reachable_moves(player).len()
+    }
+
+    /// Squares between `player`'s pieces and the nearest edges, summed over
+    /// both axes. Boards without edges have no center: always 0 there.
+    pub fn centralization(&self, player: &Player) -> usize {
+        if self.topology == Topology::Toroidal {
+            return 0;
+        }
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        let mut total = 0;
+
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, piece) in row.iter().enumerate() {
+                if piece.owner().as_ref() == Some(player) {
+                    total += x.min(width - 1 - x) + y.min(height - 1 - y);
+                }
+            }
+        }
+
+        total
+    }
+
+    /// Number of `player`'s pieces another of their pieces could leap to,
+    /// i.e. recapture on.
+    pub fn clusters(&self, player: &Player) -> usize {
+        let mut defended = vec![];
+
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, piece) in row.iter().enumerate() {
+                let kind = match piece {
+                    Piece::Leaper { kind, owner } if owner == player => &self.kinds[*kind as usize],
+                    _ => continue,
+                };
+
+                let from = Position {
+                    x: x as u8,
+                    y: y as u8,
+                };
+
+                for (dx, dy) in leap_offsets(&kind.leaps) {
+                    let to = self.topology.shift(&from, dx, dy, self.width, self.height);
+
+                    if let Some(to) = to {
+                        let target = &self.board[to.y as usize][to.x as usize];
+
+                        if target.owner().as_ref() == Some(player) && !defended.contains(&to) {
+                            defended.push(to);
+                        }
+                    }
+                }
+            }
+        }
+
+        defended.len()
+    }
+
+    fn reachable_moves(&self, player: &Player) -> Vec<Move> {
+        let mut moves = vec![];
+
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, piece) in row.iter().enumerate() {
+                let kind = match piece {
+                    Piece::Leaper { kind, owner } if owner == player => &self.kinds[*kind as usize],
+                    _ => continue,
+                };
+
+                let from = Position {
+                    x: x as u8,
+                    y: y as u8,
+                };
+
+                for (dx, dy) in leap_offsets(&kind.leaps) {
+                    let to = self.topology.shift(&from, dx, dy, self.width, self.height);
+
+                    if let Some(to) = to {
+                        let mv = Move {
+                            player: player.clone(),
+                            from: from.clone(),
+                            to,
+                        };
+
+                        if self.is_reachable(&mv) && !moves.contains(&mv) {
+                            moves.push(mv);
+                        }
+                    }
+                }
+            }
+        }
+
+        moves
+    }
+
+    /// All moves `player` can make now.
+    pub fn legal_moves_for(&self, player: &Player) -> Vec<Move> {
+        if self.finished {
+            return vec![];
+        }
+
+        let moves = self.pseudo_legal_moves(player);
+
+        if R::compulsory_captures() && moves.iter().any(|mv| self.is_capture(mv)) {
+            moves.into_iter().filter(|mv| self.is_capture(mv)).collect()
+        } else {
+            moves
+        }
+    }
+
+    fn is_legal(&self, mv: &Move) -> bool {
+        if R::compulsory_captures() {
+            self.legal_moves_for(&mv.player).contains(mv)
+        } else {
+            !self.finished && self.is_pseudo_legal(mv)
         }
     }
-}
 
-impl GameState {
     pub fn make_move(&mut self, mv: Move) {
-        if mv.player != self.current_player {
+        if !self.is_legal(&mv) {
             return;
         }
+        self.hint = None;
+
+        if self.simultaneous {
+            self.ready.push(mv.player.clone());
+            self.pending.push(mv);
+
+            if self.ready.len() == self.active_players().len() {
+                self.resolve_turn();
+            }
 
-        if !mv.valid() {
             return;
         }
 
-        let from_x = mv.from.x as usize;
-        let from_y = mv.from.y as usize;
+        if self.is_capture(&mv) {
+            self.add_capture(&mv.player);
+        }
+
+        self.move_piece(&mv);
 
-        let from_piece = self.board[from_y][from_x].clone();
+        self.move_history.push(mv.clone());
+
+        self.set_winner();
+        self.pass_turn();
+        self.set_winner();
+    }
 
-        if from_piece == Piece::Empty {
+    /// Gives the turn to the next player still in the game,
+    /// players found without moves on their turn are eliminated.
+    fn pass_turn(&mut self) {
+        if self.finished {
             return;
         }
 
-        let to_x = mv.to.x as usize;
-        let to_y = mv.to.y as usize;
+        let mover = self.current_player.clone();
+        let mut seat = self.seat(&mover);
 
-        let to_piece = self.board[to_y][to_x].clone();
-
-        if from_piece == to_piece {
+        if self.extra_moves[seat] > 0 && !self.legal_moves_for(&mover).is_empty() {
+            self.extra_moves[seat] -= 1;
             return;
         }
 
-        self.board[from_y][from_x] = Piece::Empty;
-        self.board[to_y][to_x] = from_piece;
+        for _ in 0..self.players.len() {
+            seat = (seat + 1) % self.players.len();
+            let player = self.players[seat].clone();
 
-        self.current_player = match self.current_player {
-            Player::White => Player::Black,
-            Player::Black => Player::White,
-        };
+            if self.is_out(&player) {
+                continue;
+            }
 
-        self.move_history.push(mv.clone());
+            self.current_player = player.clone();
+
+            if !self.legal_moves_for(&player).is_empty() {
+                return;
+            }
+
+            self.eliminated.push(player);
+
+            // Leaving every opponent without moves wins, even if the
+            // mover's side would be stuck too.
+            if self
+                .players
+                .iter()
+                .filter(|other| !self.allies(&mover, other))
+                .all(|other| self.is_out(other))
+            {
+                return;
+            }
+        }
+    }
+
+    fn add_capture(&mut self, player: &Player) {
+        let seat = self.seat(player);
+        self.captures[seat] += 1;
+    }
+
+    fn move_piece(&mut self, mv: &Move) {
+        let from_piece = std::mem::replace(
+            &mut self.board[mv.from.y as usize][mv.from.x as usize],
+            Piece::Empty,
+        );
+
+        self.board[mv.to.y as usize][mv.to.x as usize] = from_piece;
+    }
+
+    /// Applies moves submitted by all players in simultaneous games.
+    /// Moves were checked against the board before the turn, then:
+    /// - pieces landing on the same square destroy each other;
+    /// - pieces swapping squares pass each other and all survive;
+    /// - piece landing where another piece left captures nothing;
+    /// - piece landing on a piece that stayed captures it, as usual.
+    fn resolve_turn(&mut self) {
+        let mut moves = std::mem::take(&mut self.pending);
+        moves.sort_by_key(|mv| self.seat(&mv.player));
+        self.ready.clear();
+
+        let collided: Vec<bool> = moves
+            .iter()
+            .map(|mv| moves.iter().filter(|other| other.to == mv.to).count() > 1)
+            .collect();
+
+        for (mv, collision) in moves.iter().zip(&collided) {
+            let target = &self.board[mv.to.y as usize][mv.to.x as usize];
+            let vacated = moves.iter().any(|other| other.from == mv.to);
+
+            if *collision || (target.owner().is_some() && !vacated) {
+                self.add_capture(&mv.player);
+            }
+        }
+
+        let pieces: Vec<Piece> = moves
+            .iter()
+            .map(|mv| {
+                std::mem::replace(
+                    &mut self.board[mv.from.y as usize][mv.from.x as usize],
+                    Piece::Empty,
+                )
+            })
+            .collect();
+
+        for ((mv, piece), collision) in moves.iter().zip(pieces).zip(collided) {
+            self.board[mv.to.y as usize][mv.to.x as usize] =
+                if collision { Piece::Empty } else { piece };
+        }
+
+        self.move_history.extend(moves);
+
+        let stuck: Vec<Player> = self
+            .active_players()
+            .into_iter()
+            .filter(|player| self.legal_moves_for(player).is_empty())
+            .collect();
+        self.eliminated.extend(stuck);
 
         self.set_winner();
     }
 
-    fn count_pieces(&self) -> (u8, u8) {
-        let mut count_white = 0;
-        let mut count_black = 0;
+    pub fn count_pieces(&self, player: &Player) -> u8 {
+        let own = Some(player.clone());
 
-        for row in &self.board {
-            for piece in row {
-                match piece {
-                    Piece::White => count_white += 1,
-                    Piece::Black => count_black += 1,
-                    _ => {}
+        self.board
+            .iter()
+            .flatten()
+            .filter(|piece| piece.owner() == own)
+            .count() as u8
+    }
+
+    /// Whether `player` lost their place in the game: was left without moves
+    /// or, depending on the victory condition, without enough pieces.
+    pub fn is_out(&self, player: &Player) -> bool {
+        if self.eliminated.contains(player) {
+            return true;
+        }
+
+        match self.victory {
+            Victory::MinPieces(count) => self.count_pieces(player) < count,
+            Victory::CaptureAll => self.count_pieces(player) == 0,
+            _ => false,
+        }
+    }
+
+    /// Players still in the game, in turn order.
+    fn active_players(&self) -> Vec<Player> {
+        self.players
+            .iter()
+            .filter(|player| !self.is_out(player))
+            .cloned()
+            .collect()
+    }
+
+    /// Line `player` has to reach for `Victory::BackRank`: home row
+    /// of the player sitting across the board.
+    fn is_target_line(&self, player: &Player, pos: &Position) -> bool {
+        match player {
+            Player::White => pos.y == 0,
+            Player::Black => pos.y == self.height - 1,
+            Player::Red => pos.x == self.width - 1,
+            Player::Blue => pos.x == 0,
+        }
+    }
+
+    /// Squares in the middle of the board: 2x2 or fewer on odd sizes,
+    /// blocked squares excluded.
+    fn center(&self) -> Vec<Position> {
+        let mut center = vec![];
+
+        for y in (self.height - 1) / 2..=self.height / 2 {
+            for x in (self.width - 1) / 2..=self.width / 2 {
+                if self.board[y as usize][x as usize] != Piece::Blocked {
+                    center.push(Position { x, y });
                 }
             }
         }
 
-        (count_white, count_black)
+        center
+    }
+
+    /// Whether `player` fulfilled the victory condition,
+    /// being the last team in the game always counts.
+    fn achieved(&self, player: &Player) -> bool {
+        let opponents_out = self
+            .players
+            .iter()
+            .filter(|other| !self.allies(player, other))
+            .all(|other| self.is_out(other));
+
+        if opponents_out {
+            return true;
+        }
+
+        let own = Some(player.clone());
+
+        match self.victory {
+            Victory::MinPieces(_) | Victory::CaptureAll | Victory::Immobilize => false,
+            Victory::BackRank(count) => {
+                let mut on_target = 0;
+
+                for (y, row) in self.board.iter().enumerate() {
+                    for (x, piece) in row.iter().enumerate() {
+                        let pos = Position {
+                            x: x as u8,
+                            y: y as u8,
+                        };
+
+                        if piece.owner() == own && self.is_target_line(player, &pos) {
+                            on_target += 1;
+                        }
+                    }
+                }
+
+                on_target >= count
+            }
+            Victory::KingOfTheHill => {
+                let center = self.center();
+                !center.is_empty()
+                    && center
+                        .iter()
+                        .all(|pos| self.board[pos.y as usize][pos.x as usize].owner() == own)
+            }
+            Victory::Captures(count) => self.captures[self.seat(player)] >= count,
+        }
+    }
+
+    /// Winner by the victory condition, checked in turn order
+    /// starting from the player who moved last.
+    pub fn victory_winner(&self) -> Option<Player> {
+        let first = match self.move_history.last() {
+            Some(mv) => self.seat(&mv.player),
+            None => 0,
+        };
+
+        (0..self.players.len())
+            .map(|offset| &self.players[(first + offset) % self.players.len()])
+            .find(|player| self.achieved(player))
+            .cloned()
     }
 
     fn set_winner(&mut self) {
@@ -98,18 +774,214 @@ impl GameState {
             return;
         }
 
-        let (count_white, count_black) = self.count_pieces();
+        let mut winning_teams: Vec<&[Player]> = vec![];
 
-        self.finished = (count_black < 4) || (count_white < 4);
-        self.winner = {
-            if count_black < 4 {
-                Some(Player::White)
-            } else if count_white < 4 {
-                Some(Player::Black)
-            } else {
-                None
+        for player in self.players.iter().filter(|player| self.achieved(player)) {
+            let team = self.team(player);
+
+            if !winning_teams.contains(&team) {
+                winning_teams.push(team);
             }
+        }
+
+        // Several teams reached the goal in the same turn, or nobody is left: draw.
+        if (self.simultaneous && winning_teams.len() > 1) || self.active_players().is_empty() {
+            self.finished = true;
+            return;
+        }
+
+        self.winner = R::winner(self);
+        self.finished |= self.winner.is_some();
+    }
+
+    /// Squares `player` can see: occupied by pieces of their team or reachable by them.
+    fn visible_squares(&self, player: &Player) -> Vec<Vec<bool>> {
+        let mut visible = vec![vec![false; self.width as usize]; self.height as usize];
+
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, piece) in row.iter().enumerate() {
+                let kind = match piece {
+                    Piece::Leaper { kind, owner } if self.allies(owner, player) => {
+                        &self.kinds[*kind as usize]
+                    }
+                    _ => continue,
+                };
+
+                let from = Position {
+                    x: x as u8,
+                    y: y as u8,
+                };
+                visible[y][x] = true;
+
+                for (dx, dy) in leap_offsets(&kind.leaps) {
+                    if let Some(to) = self.topology.shift(&from, dx, dy, self.width, self.height) {
+                        visible[to.y as usize][to.x as usize] = true;
+                    }
+                }
+            }
+        }
+
+        visible
+    }
+
+    /// State as seen by `viewer` (`None` for spectators).
+    /// In fog games squares the viewer's team can't see are `Piece::Hidden` and
+    /// opponents' moves are left out of history. Spectators see everything unless
+    /// `fog_spectators` is set, then only squares seen by all players.
+    /// Finished games are shown in full.
+    pub fn view_for(&self, viewer: Option<&Player>, fog_spectators: bool) -> GameState<R> {
+        let mut view = self.clone();
+        // Moves submitted in simultaneous games stay secret from everyone.
+        view.pending.clear();
+        if view.hint.as_ref().map(|hint| &hint.player) != viewer {
+            view.hint = None;
+        }
+
+        if !self.fog || self.finished {
+            return view;
+        }
+
+        let visible = match viewer {
+            Some(player) => self.visible_squares(player),
+            None if fog_spectators => {
+                let mut visible = vec![vec![true; self.width as usize]; self.height as usize];
+
+                for player in &self.players {
+                    let seen = self.visible_squares(player);
+
+                    for (row, seen_row) in visible.iter_mut().zip(seen) {
+                        for (square, seen) in row.iter_mut().zip(seen_row) {
+                            *square &= seen;
+                        }
+                    }
+                }
+
+                visible
+            }
+            None => return view,
         };
+
+        for (row, visible_row) in view.board.iter_mut().zip(visible) {
+            for (piece, visible) in row.iter_mut().zip(visible_row) {
+                // Obstacles are part of the map, so they are always known.
+                if !visible && *piece != Piece::Blocked {
+                    *piece = Piece::Hidden;
+                }
+            }
+        }
+
+        if let Some(viewer) = viewer {
+            view.move_history
+                .retain(|mv| self.allies(&mv.player, viewer));
+        } else {
+            view.move_history.clear();
+        }
+
+        view
+    }
+
+    /// Zobrist hash of everything deciding how the game goes on from here:
+    /// board, player to move and per-player counters.
+    pub fn hash(&self) -> u64 {
+        let mut hash = 0;
+
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, piece) in row.iter().enumerate() {
+                let (x, y) = (x as u8, y as u8);
+
+                hash ^= match piece {
+                    Piece::Empty => 0,
+                    Piece::Blocked => zobrist::blocked(x, y),
+                    Piece::Hidden => zobrist::hidden(x, y),
+                    Piece::Leaper { kind, owner } => zobrist::piece(x, y, *kind, owner.index()),
+                };
+            }
+        }
+
+        if let Some(player) = self.to_move() {
+            hash ^= zobrist::to_move(player.index());
+        }
+
+        for player in &self.ready {
+            hash ^= zobrist::ready(player.index());
+        }
+
+        for player in &self.eliminated {
+            hash ^= zobrist::eliminated(player.index());
+        }
+
+        for (seat, player) in self.players.iter().enumerate() {
+            hash ^= zobrist::captures(player.index(), self.captures[seat]);
+            hash ^= zobrist::extra_moves(player.index(), self.extra_moves[seat]);
+        }
+
+        for mv in &self.pending {
+            hash ^= zobrist::pending((mv.from.x, mv.from.y), (mv.to.x, mv.to.y));
+        }
+
+        hash
+    }
+
+    /// Knights as tablebases index them, `None` unless this is a two-player
+    /// game of knights under the classic rules.
+    pub fn knights(&self) -> Option<Knights> {
+        let knight = |owner| Piece::Leaper { kind: 0, owner };
+
+        if self.finished
+            || self.players != PLAYERS
+            || self.fog
+            || self.simultaneous
+            || self.topology != Topology::Bounded
+            || self.kinds != [PieceKind::knight()]
+            || R::compulsory_captures()
+            || !R::can_capture(&knight(Player::White), &knight(Player::Black))
+            || self.extra_moves.iter().any(|&moves| moves > 0)
+            || usize::from(self.width) * usize::from(self.height) > 64
+        {
+            return None;
+        }
+
+        let mut knights = Knights {
+            white: 0,
+            black: 0,
+            white_to_move: self.current_player == Player::White,
+        };
+
+        for (y, row) in self.board.iter().enumerate() {
+            for (x, piece) in row.iter().enumerate() {
+                let bit = 1 << (x + y * usize::from(self.width));
+
+                match piece {
+                    Piece::Empty => {}
+                    Piece::Leaper {
+                        owner: Player::White,
+                        ..
+                    } => knights.white |= bit,
+                    Piece::Leaper {
+                        owner: Player::Black,
+                        ..
+                    } => knights.black |= bit,
+                    _ => return None,
+                }
+            }
+        }
+
+        Some(knights)
+    }
+
+    /// Attaches opening book moves from the position to a view of it.
+    pub fn set_explorer(&mut self, moves: Vec<BookMove>) {
+        self.explorer = moves;
+    }
+
+    /// Ends the game with a result decided outside the rules, e.g. by a tablebase.
+    pub fn adjudicate(&mut self, winner: Option<Player>) {
+        self.finished = true;
+        self.winner = winner;
+    }
+
+    pub fn get_history(&self) -> &History {
+        &self.move_history
     }
 
     pub fn get_winner(&self) -> Option<Player> {
@@ -120,3 +992,184 @@ impl GameState {
         self.finished
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::{Fog, Giveaway, Simultaneous, Teams};
+
+    /// Start of variant `R` with only the given pieces and obstacles left.
+    fn position<R: Ruleset>(victory: Victory, pieces: &[(Player, u8, u8)]) -> GameState<R> {
+        let (width, height) = R::default_size();
+        let mut state = GameState::<R>::new(width, height).with_victory(victory);
+
+        for piece in state.board.iter_mut().flatten() {
+            if piece.owner().is_some() {
+                *piece = Piece::Empty;
+            }
+        }
+        for (owner, x, y) in pieces {
+            state.board[*y as usize][*x as usize] = Piece::Leaper {
+                kind: 0,
+                owner: owner.clone(),
+            };
+        }
+
+        state
+    }
+
+    fn mv(player: Player, from: (u8, u8), to: (u8, u8)) -> Move {
+        Move {
+            player,
+            from: Position {
+                x: from.0,
+                y: from.1,
+            },
+            to: Position { x: to.0, y: to.1 },
+        }
+    }
+
+    #[test]
+    fn teams_cannot_capture_allies() {
+        let mut state = position::<Teams>(
+            Victory::CaptureAll,
+            &[
+                (Player::White, 4, 4),
+                (Player::Black, 5, 6),
+                (Player::Red, 6, 5),
+                (Player::Blue, 1, 1),
+            ],
+        );
+
+        let moves = state.legal_moves_for(&Player::White);
+        assert!(!moves.contains(&mv(Player::White, (4, 4), (5, 6))));
+        assert!(moves.contains(&mv(Player::White, (4, 4), (6, 5))));
+
+        state.make_move(mv(Player::White, (4, 4), (5, 6)));
+        assert!(state.get_history().is_empty());
+        assert_eq!(state.count_pieces(&Player::Black), 1);
+
+        state.make_move(mv(Player::White, (4, 4), (6, 5)));
+        assert_eq!(state.count_pieces(&Player::Red), 0);
+        assert!(state.allies(&Player::White, &Player::Black));
+        assert!(!state.is_finished());
+    }
+
+    #[test]
+    fn giveaway_inverts_the_winner() {
+        let mut state = position::<Giveaway>(
+            Victory::MinPieces(2),
+            &[
+                (Player::White, 0, 0),
+                (Player::White, 7, 0),
+                (Player::Black, 1, 2),
+                (Player::Black, 7, 7),
+            ],
+        );
+
+        // The capture is compulsory.
+        let capture = mv(Player::White, (0, 0), (1, 2));
+        assert_eq!(state.legal_moves_for(&Player::White), vec![capture.clone()]);
+
+        state.make_move(capture);
+        assert_eq!(state.victory_winner(), Some(Player::White));
+        assert_eq!(state.get_winner(), Some(Player::Black));
+        assert!(state.is_finished());
+    }
+
+    #[test]
+    fn simultaneous_collision_destroys_both() {
+        let mut state = position::<Simultaneous>(
+            Victory::CaptureAll,
+            &[
+                (Player::White, 0, 0),
+                (Player::White, 7, 0),
+                (Player::Black, 2, 4),
+                (Player::Black, 7, 7),
+            ],
+        );
+
+        state.make_move(mv(Player::White, (0, 0), (1, 2)));
+        // Nothing happens until both moved.
+        assert!(state.get_history().is_empty());
+        assert_eq!(state.board[2][1], Piece::Empty);
+        assert_ne!(state.board[0][0], Piece::Empty);
+
+        state.make_move(mv(Player::Black, (2, 4), (1, 2)));
+        assert_eq!(state.get_history().len(), 2);
+        assert_eq!(state.board[2][1], Piece::Empty);
+        assert_eq!(state.count_pieces(&Player::White), 1);
+        assert_eq!(state.count_pieces(&Player::Black), 1);
+        assert_eq!(state.captures, vec![1, 1]);
+        assert!(state.is_to_move(&Player::White) && state.is_to_move(&Player::Black));
+        assert!(!state.is_finished());
+    }
+
+    /// Fog game after White's (0, 0) -> (1, 2) and Black's (7, 7) -> (6, 5).
+    /// Both see (4, 4), the obstacle on (4, 0) is out of everyone's sight.
+    fn fog_game() -> GameState<Fog> {
+        let mut state = position::<Fog>(
+            Victory::CaptureAll,
+            &[
+                (Player::White, 0, 0),
+                (Player::White, 2, 3),
+                (Player::Black, 7, 7),
+            ],
+        );
+        state.board[0][4] = Piece::Blocked;
+        state.make_move(mv(Player::White, (0, 0), (1, 2)));
+        state.make_move(mv(Player::Black, (7, 7), (6, 5)));
+        state
+    }
+
+    #[test]
+    fn fog_hides_squares_out_of_sight() {
+        let state = fog_game();
+        let view = state.view_for(Some(&Player::White), false);
+
+        assert_eq!(view.board[2][1], state.board[2][1]);
+        assert_eq!(view.board[4][4], Piece::Empty);
+        assert_eq!(view.board[5][6], Piece::Hidden);
+        assert_eq!(view.board[7][7], Piece::Hidden);
+        assert_eq!(view.board[0][4], Piece::Blocked);
+        assert_eq!(view.get_history(), &vec![mv(Player::White, (0, 0), (1, 2))]);
+
+        let view = state.view_for(Some(&Player::Black), false);
+        assert_eq!(view.board[5][6], state.board[5][6]);
+        assert_eq!(view.board[2][1], Piece::Hidden);
+        assert_eq!(view.get_history(), &vec![mv(Player::Black, (7, 7), (6, 5))]);
+    }
+
+    #[test]
+    fn fog_spectators_see_what_everyone_sees() {
+        let state = fog_game();
+
+        let view = state.view_for(None, false);
+        assert_eq!(view.board, state.board);
+        assert_eq!(view.get_history().len(), 2);
+
+        let view = state.view_for(None, true);
+        assert_eq!(view.board[4][4], Piece::Empty);
+        assert_eq!(view.board[2][1], Piece::Hidden);
+        assert_eq!(view.board[5][6], Piece::Hidden);
+        assert_eq!(view.board[0][4], Piece::Blocked);
+        assert!(view.get_history().is_empty());
+    }
+
+    #[test]
+    fn submitted_moves_stay_secret() {
+        let mut state = position::<Simultaneous>(
+            Victory::CaptureAll,
+            &[(Player::White, 0, 0), (Player::Black, 7, 7)],
+        );
+        state.make_move(mv(Player::White, (0, 0), (1, 2)));
+        assert_eq!(state.pending.len(), 1);
+
+        for viewer in &[Some(Player::White), Some(Player::Black), None] {
+            for &fog_spectators in &[false, true] {
+                let view = state.view_for(viewer.as_ref(), fog_spectators);
+                assert!(view.pending.is_empty());
+            }
+        }
+    }
+}
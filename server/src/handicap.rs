@@ -0,0 +1,84 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::Player;
+
+/// Odds given to balance a game between players of different strength.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Handicap {
+    /// Player starts without N of their knights.
+    Knights(Player, u8),
+    /// Player makes N moves in a row on their first turn.
+    ExtraMoves(Player, u8),
+}
+
+/// Rough Elo worth of a knight given away and of an extra move, used to
+/// rate games played with odds. `horse-tournament --handicap` can measure
+/// them between equal engines.
+pub const KNIGHT_ELO: f64 = 200.0;
+pub const EXTRA_MOVE_ELO: f64 = 50.0;
+
+pub const HANDICAP_HELP: &str =
+    "PLAYER:knights:N, PLAYER:moves:N (PLAYER is white, black, red or blue)";
+
+impl Handicap {
+    /// Elo the handicap costs `player` in a two-player game, negative if it
+    /// favours them.
+    pub fn odds(&self, player: &Player) -> f64 {
+        let (named, cost) = match self {
+            Handicap::Knights(named, count) => (named, f64::from(*count) * KNIGHT_ELO),
+            Handicap::ExtraMoves(named, count) => (named, -f64::from(*count) * EXTRA_MOVE_ELO),
+        };
+
+        if named == player {
+            cost
+        } else {
+            -cost
+        }
+    }
+}
+
+impl FromStr for Handicap {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Handicap, String> {
+        let unknown = || {
+            format!(
+                "Unknown handicap '{}', expected one of: {}",
+                s, HANDICAP_HELP
+            )
+        };
+        let parts: Vec<&str> = s.split(':').collect();
+
+        let (player, name, count) = match parts.as_slice() {
+            [player, name, count] => (player.parse::<Player>()?, *name, *count),
+            _ => return Err(unknown()),
+        };
+        let count = count
+            .parse::<u8>()
+            .ok()
+            .filter(|&count| count > 0)
+            .ok_or_else(|| format!("'{}' is not a positive number", count))?;
+
+        match name {
+            "knights" => Ok(Handicap::Knights(player, count)),
+            "moves" => Ok(Handicap::ExtraMoves(player, count)),
+            _ => Err(unknown()),
+        }
+    }
+}
+
+impl fmt::Display for Handicap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Handicap::Knights(player, count) => {
+                write!(f, "{:?} plays without {} knights", player, count)
+            }
+            Handicap::ExtraMoves(player, count) => {
+                write!(f, "{:?} makes {} extra moves", player, count)
+            }
+        }
+    }
+}
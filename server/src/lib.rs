@@ -0,0 +1,23 @@
+//! Game rules, engines and protocol shared by the server and the tools
+//! built around it.
+
+extern crate crossbeam;
+extern crate serde;
+
+pub mod analysis;
+pub mod book;
+pub mod engine;
+pub mod evaluation;
+pub mod external;
+pub mod gamesate;
+pub mod handicap;
+pub mod mcts;
+pub mod notation;
+pub mod options;
+pub mod protocol;
+pub mod rules;
+pub mod tablebase;
+pub mod tt;
+pub mod types;
+pub mod victory;
+pub mod zobrist;
@@ -1,18 +1,32 @@
-extern crate serde;
-#[macro_use]
 extern crate crossbeam;
+extern crate horse_server;
+extern crate serde;
 
-mod gamesate;
-mod types;
+use crossbeam::channel::{bounded, unbounded, Receiver, Sender};
+use horse_server::{
+    analysis, book, engine, evaluation, external, gamesate, mcts, options, protocol, rules,
+    tablebase, types, with_variant,
+};
 
-use crossbeam::channel::{bounded, Sender};
+use book::Book;
+use engine::{Difficulty, Engine, Ponder};
+use evaluation::Weights;
+use external::External;
 use gamesate::GameState;
+use mcts::Mcts;
+use options::{parse_options, BotKind, Options, USAGE};
+use protocol::{ClientMessage, Hello, Request};
+use rules::*;
 use serde::Serialize;
+use std::fs::OpenOptions;
 use std::io::{BufRead, BufReader, Write};
 use std::net::{SocketAddr, TcpListener, TcpStream};
 use std::process;
+use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, RwLock};
 use std::thread;
+use std::time::{Duration, Instant};
+use tablebase::{Tablebase, Value};
 use types::*;
 
 /// Writes serializable data to TcpStream using \n as separator.
@@ -23,25 +37,113 @@ fn write_json_data(stream: &mut TcpStream, data: &impl Serialize) {
     let _ = stream.write_all(&buffer);
 }
 
+/// View of the game for `viewer`, listing the book's moves from the
+/// position when `explorer` is given. Fog games get none until they end,
+/// the moves would give hidden pieces away.
+fn view_with_explorer<R: Ruleset>(
+    game_state: &GameState<R>,
+    viewer: Option<&Player>,
+    fog_spectators: bool,
+    explorer: Option<&Book>,
+) -> GameState<R> {
+    let mut view = game_state.view_for(viewer, fog_spectators);
+    if let Some(book) = explorer {
+        if !R::fog() || game_state.is_finished() {
+            view.set_explorer(book.moves(game_state));
+        }
+    }
+    view
+}
+
+/// Appends `text` to the file at `path`, creating it if needed.
+fn append(path: &str, text: &str) -> Result<(), String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Appends a finished game to the archive file as a line of JSON.
+fn archive<R: Ruleset>(game_state: &GameState<R>, path: &str) -> Result<(), String> {
+    let mut line = serde_json::to_string(game_state).expect("Serialization errror");
+    line.push('\n');
+    append(path, &line)
+}
+
+/// Appends the record of a finished game annotated by `engine` to the
+/// file at `path`, followed by an empty line.
+fn analyze<R: Ruleset>(
+    game_state: &GameState<R>,
+    engine: &Engine,
+    path: &str,
+) -> Result<(), String> {
+    let annotations = analysis::analyze(game_state, engine)?;
+    let mut record = analysis::record(game_state, &annotations);
+    record.push('\n');
+    append(path, &record)
+}
+
+/// Time a client has to send `Hello` before being seated as a human.
+const HELLO_TIMEOUT: Duration = Duration::from_millis(500);
+/// Search time for each position of a game analyzed with `--analysis`.
+const ANALYSIS_TIME: Duration = Duration::from_millis(200);
+
 enum ChannelMsg<T> {
     Close,
+    /// Every seat for a connection is taken.
+    Start,
+    /// Move found for a hint asked for with `ply` moves played.
+    Hint {
+        player: Player,
+        ply: usize,
+        mv: Option<Move>,
+    },
     Msg(T),
 }
 
-/// Handles data from player: parses moves from TcpStream and sending them to channel.
+/// Reads the optional `Hello` a client sends right after connecting. Any
+/// other first line is returned to be handled as usual.
+fn read_hello(reader: &mut BufReader<TcpStream>) -> (Option<Hello>, String) {
+    let mut line = vec![];
+
+    let _ = reader.get_ref().set_read_timeout(Some(HELLO_TIMEOUT));
+    let read = reader.read_until(b'\n', &mut line);
+    let _ = reader.get_ref().set_read_timeout(None);
+    // A line cut by the timeout is finished, its start is already consumed.
+    if read.is_err() && !line.is_empty() {
+        let _ = reader.read_until(b'\n', &mut line);
+    }
+
+    let line = String::from_utf8_lossy(&line).into_owned();
+    match serde_json::from_str(line.trim()) {
+        Ok(Request::Hello(hello)) => (Some(hello), String::new()),
+        _ => (None, line),
+    }
+}
+
+/// Handles data from player: parses messages from TcpStream, starting with
+/// `first_line` if it isn't empty, and sending them to channel.
 fn handle_players_moves(
     player: Player,
-    stream: TcpStream,
-    updates_chan: Sender<ChannelMsg<Option<Move>>>,
+    mut reader: BufReader<TcpStream>,
+    first_line: String,
+    updates_chan: Sender<ChannelMsg<Option<ClientMessage>>>,
 ) {
+    let mut first_line = Some(first_line).filter(|line| !line.is_empty());
     let mut buffer = String::new();
-    let mut reader = BufReader::new(&stream);
 
     loop {
         buffer.clear();
-        reader
-            .read_line(&mut buffer)
-            .expect("Error while getting data from client");
+        match first_line.take() {
+            Some(line) => buffer = line,
+            None => {
+                reader
+                    .read_line(&mut buffer)
+                    .expect("Error while getting data from client");
+            }
+        }
         let buffer = buffer.trim();
 
         if buffer.is_empty() {
@@ -49,16 +151,12 @@ fn handle_players_moves(
             return;
         }
 
-        let msg: Result<Move, _> = serde_json::from_str(&buffer);
+        let msg: Result<ClientMessage, _> = serde_json::from_str(buffer);
 
         let msg = match msg {
-            Ok(msg) => {
-                if msg.player == player {
-                    Some(msg)
-                } else {
-                    None
-                }
-            }
+            Ok(ClientMessage::Move(mv)) if mv.player != player => None,
+            Ok(ClientMessage::Request(Request::Hint(asking))) if asking != player => None,
+            Ok(msg) => Some(msg),
             _ => None,
         };
 
@@ -68,23 +166,317 @@ fn handle_players_moves(
     }
 }
 
+/// Plays for `player` with an engine of given kind: searches every state
+/// received where it's their turn and sends the move found.
+///
+/// With `ponder` the alpha-beta engine goes on searching the position after
+/// the reply it expects, and if the reply is played continues that search
+/// instead of starting afresh.
+fn play_bot<R: Ruleset>(
+    player: Player,
+    kind: BotKind,
+    engine: Arc<RwLock<Engine>>,
+    mcts: Mcts,
+    ponder: bool,
+    states: Receiver<GameState<R>>,
+    updates_chan: Sender<ChannelMsg<Option<ClientMessage>>>,
+) {
+    // Length of history the last move was found for: the same state may be
+    // sent again before the move is applied.
+    let mut moved_at = None;
+    let mut pondering: Option<Ponder> = None;
+    let mut external = match &kind {
+        BotKind::External(command) => match External::spawn(command, &player) {
+            Ok(bot) => {
+                println!("{:?} bot: {}", player, bot.name);
+                Some(bot)
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                let _ = updates_chan.send(ChannelMsg::Close);
+                return;
+            }
+        },
+        _ => None,
+    };
+
+    while let Ok(mut state) = states.recv() {
+        // Only the latest state matters.
+        if let Some(latest) = states.try_iter().last() {
+            state = latest;
+        }
+
+        if moved_at == Some(state.get_history().len()) {
+            continue;
+        }
+
+        // Pondering goes on until it's our turn.
+        if state.to_move().as_ref() != Some(&player) {
+            if state.is_finished() {
+                pondering = None;
+            }
+            continue;
+        }
+
+        let mv = match kind {
+            BotKind::AlphaBeta => {
+                let engine = engine.read().unwrap().clone();
+                let deadline = Instant::now() + engine.time_limit;
+                let result = pondering
+                    .take()
+                    .filter(|ponder| ponder.hit(&state))
+                    .and_then(|ponder| ponder.finish(deadline))
+                    .filter(|result| result.best.is_some())
+                    .unwrap_or_else(|| {
+                        engine.search(&state, &player, deadline, &AtomicBool::new(false))
+                    });
+
+                if let (true, Some(mv), Some(reply)) = (ponder, &result.best, result.ponder) {
+                    let mut next = state.clone();
+                    next.make_move(mv.clone());
+                    next.make_move(reply);
+                    if next.to_move().as_ref() == Some(&player) {
+                        pondering = Some(engine.ponder(next, player.clone()));
+                    }
+                }
+
+                result.best
+            }
+            BotKind::Mcts => mcts.best_move(&state, &player),
+            BotKind::Remote => unreachable!("remote bots play over TCP"),
+            BotKind::External(_) => {
+                let bot = external.as_mut().expect("external bot is started");
+                let movetime = engine.read().unwrap().time_limit;
+                match bot.best_move(&state, &player, movetime) {
+                    Ok(mv) => Some(mv),
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        let _ = updates_chan.send(ChannelMsg::Close);
+                        return;
+                    }
+                }
+            }
+        };
+
+        if let Some(mv) = mv {
+            moved_at = Some(state.get_history().len());
+            let msg = ChannelMsg::Msg(Some(ClientMessage::Move(mv)));
+            if updates_chan.send(msg).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Ends the game if the tablebase knows its result.
+fn adjudicate<R: Ruleset>(game_state: &mut GameState<R>, tablebase: &Tablebase) {
+    let (mover, value) = match (game_state.to_move(), tablebase.probe_state(game_state)) {
+        (Some(mover), Some(value)) => (mover, value),
+        _ => return,
+    };
+
+    let winner = match value {
+        Value::Win(_) => Some(mover.clone()),
+        Value::Loss(_) => Some(mover.opponent()),
+        Value::Draw => None,
+    };
+    match &winner {
+        Some(winner) => println!("Adjudicated by the tablebase: {:?} wins", winner),
+        None => println!("Adjudicated by the tablebase: draw"),
+    }
+
+    game_state.adjudicate(winner);
+}
+
 fn main() {
+    let options = match parse_options(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+
+    with_variant!(options.variant.as_str(), run_game(&options));
+}
+
+/// Creates the game described by options.
+fn new_game<R: Ruleset>(options: &Options) -> Result<GameState<R>, String> {
+    let (width, height) = R::default_size();
+    let (width, height) = (
+        options.width.unwrap_or(width),
+        options.height.unwrap_or(height),
+    );
+    check_size::<R>(width, height)?;
+    let mut game_state = GameState::<R>::new(width, height);
+
+    if let Some(victory) = options.victory.clone() {
+        game_state = game_state.with_victory(victory);
+    }
+
+    game_state = game_state.with_obstacles(&options.obstacles)?;
+
+    if options.hints > 0 {
+        game_state = game_state.with_hints(options.hints);
+    }
+
+    for handicap in &options.handicaps {
+        game_state = game_state.with_handicap(handicap.clone())?;
+    }
+
+    game_state.check_start()?;
+
+    for bot in &options.bots {
+        if !game_state.players().contains(&bot.player) {
+            return Err(format!("{:?} doesn't play {}", bot.player, R::NAME));
+        }
+        game_state.set_bot(&bot.player);
+    }
+
+    Ok(game_state)
+}
+
+/// Accepts players and runs a single game of variant `R`.
+fn run_game<R: Ruleset>(options: &Options) {
+    let game_state = match new_game::<R>(options) {
+        Ok(game_state) => game_state,
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+    };
+    println!("Playing {}, victory: {}", R::NAME, game_state.get_victory());
+    for handicap in game_state.get_handicaps() {
+        println!("Handicap: {}", handicap);
+    }
+    if options.hints > 0 {
+        println!("Hints: {} per player", options.hints);
+    }
+    for bot in &options.bots {
+        match &bot.kind {
+            BotKind::AlphaBeta => {
+                println!(
+                    "{:?} is played by alpha-beta ({}, {} threads{})",
+                    bot.player,
+                    options.difficulty,
+                    options.threads,
+                    if options.ponder { ", pondering" } else { "" }
+                )
+            }
+            BotKind::Mcts => println!(
+                "{:?} is played by MCTS ({} playouts, exploration {})",
+                bot.player, options.mcts.playouts, options.mcts.exploration
+            ),
+            BotKind::External(command) => {
+                println!("{:?} is played by '{}'", bot.player, command)
+            }
+            BotKind::Remote => println!("{:?} is kept for a bot connecting", bot.player),
+        }
+    }
+
+    let tablebase = match options.tablebase.as_deref().map(Tablebase::load) {
+        Some(Ok(tablebase)) => {
+            println!("{:?}", tablebase);
+            if tablebase.size() != game_state.size() {
+                println!("The tablebase is for another board size and won't be used");
+            }
+            Some(Arc::new(tablebase))
+        }
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+        None => None,
+    };
+
+    let book = match options.book.as_deref().map(Book::load) {
+        Some(Ok(book)) => {
+            println!("Opening book with {} positions", book.positions());
+            Some(Arc::new(book))
+        }
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+        None => None,
+    };
+    let explorer = book.clone().filter(|_| options.explorer);
+
+    let weights = match options.eval.as_deref().map(Weights::load) {
+        Some(Ok(weights)) => {
+            println!("Evaluation weights: {:?}", weights);
+            weights
+        }
+        Some(Err(e)) => {
+            eprintln!("{}", e);
+            process::exit(2);
+        }
+        None => Weights::default(),
+    };
+
     let addr: SocketAddr = "127.0.0.1:31337".parse().unwrap();
-    let listener = TcpListener::bind(&addr).expect("unable to bind TCP listener");
+    let listener = TcpListener::bind(addr).expect("unable to bind TCP listener");
     println!("Listening on {}", addr);
 
-    // Vector of players' tcp connections.
+    // Vector of players' tcp connections with their seats.
     let connections = Arc::new(RwLock::new(vec![]));
-    let game_state = Arc::new(RwLock::new(GameState::default()));
+    let game_state = Arc::new(RwLock::new(game_state));
 
-    let (white_moves_writer, white_moves_reader) = bounded(1);
-    let (black_moves_writer, black_moves_reader) = bounded(1);
+    let (moves_writer, moves_reader) = bounded(1);
+
+    // Engine players get every update of their view of the game.
+    let engine_for = |difficulty: &Difficulty| Engine {
+        threads: options.threads,
+        tablebase: tablebase.clone(),
+        book: book.clone(),
+        weights: weights.clone(),
+        ..difficulty.engine()
+    };
+    let engine = Arc::new(RwLock::new(engine_for(&options.difficulty)));
+    let mut bots = vec![];
+    for bot in options
+        .bots
+        .iter()
+        .filter(|bot| bot.kind != BotKind::Remote)
+    {
+        let (states_writer, states_reader) = unbounded();
+        let (player, kind) = (bot.player.clone(), bot.kind.clone());
+        let chan = moves_writer.clone();
+        let engine = engine.clone();
+        let mcts = options.mcts.clone();
+        let ponder = options.ponder;
+        thread::spawn(move || play_bot(player, kind, engine, mcts, ponder, states_reader, chan));
+        bots.push((bot.player.clone(), states_writer));
+    }
+    // Seats for connections: people take free ones, bots those kept for
+    // them first.
+    let (remote, humans): (Vec<Player>, Vec<Player>) = game_state
+        .read()
+        .unwrap()
+        .players()
+        .iter()
+        .filter(|player| {
+            !options
+                .bots
+                .iter()
+                .any(|bot| bot.player == **player && bot.kind != BotKind::Remote)
+        })
+        .cloned()
+        .partition(|player| options.bots.iter().any(|bot| bot.player == *player));
+    // Bots wait for people to join, who may still pick the difficulty.
+    let mut started = remote.is_empty() && humans.is_empty();
+
+    let hints_writer = moves_writer.clone();
+    // Players whose hint is being searched.
+    let mut hinting: Vec<Player> = vec![];
 
     let conns = connections.clone();
     let gm_state = game_state.clone();
+    let fog_spectators = options.fog_spectators;
+    let explorer_book = explorer.clone();
 
     // Main server, which accepts incomming tcp connections & runs some basic logic.
-    // Runs in another thread.
+    // Runs in another thread, each connection is greeted and seated in its own.
     let _server = thread::spawn(move || {
         listener.incoming().for_each(|stream| {
             if stream.is_err() {
@@ -92,43 +484,101 @@ fn main() {
             }
 
             let mut stream = stream.unwrap();
+            let (conns, gm_state) = (conns.clone(), gm_state.clone());
+            let (remote, humans) = (remote.clone(), humans.clone());
+            let explorer_book = explorer_book.clone();
+            let moves_writer = moves_writer.clone();
 
-            {
-                let game_state = gm_state.read().unwrap();
-                let mut conns = conns.write().unwrap();
-                let player_count = conns.len();
-
-                let player = match player_count {
-                    0 => Some(Player::White),
-                    1 => Some(Player::Black),
-                    _ => None,
+            thread::spawn(move || {
+                let mut reader =
+                    BufReader::new(stream.try_clone().expect("IO error: clonning TcpStream"));
+                let (hello, first_line) = read_hello(&mut reader);
+                let mut messages = vec![];
+
+                let player = {
+                    let mut game_state = gm_state.write().unwrap();
+                    let mut conns = conns.write().unwrap();
+                    // Seats are taken in turn order, everyone else spectates.
+                    let is_free = |seat: &&Player| {
+                        !conns
+                            .iter()
+                            .any(|(player, _): &(Option<Player>, TcpStream)| {
+                                player.as_ref() == Some(*seat)
+                            })
+                    };
+                    let is_bot = hello.as_ref().is_some_and(|hello| hello.bot);
+                    let player = if is_bot {
+                        remote.iter().chain(&humans).find(is_free)
+                    } else {
+                        humans.iter().find(is_free)
+                    }
+                    .cloned();
+
+                    if let (Some(hello), Some(player)) = (&hello, &player) {
+                        println!(
+                            "{:?} is {}{}",
+                            player,
+                            hello.name,
+                            if is_bot { " (bot)" } else { "" }
+                        );
+                        if is_bot {
+                            game_state.set_bot(player);
+                        }
+                    }
+
+                    // Sending player & game state info to new player
+                    write_json_data(&mut stream, &player);
+                    write_json_data(
+                        &mut stream,
+                        &view_with_explorer(
+                            &game_state,
+                            player.as_ref(),
+                            fog_spectators,
+                            explorer_book.as_deref(),
+                        ),
+                    );
+
+                    if player.is_some() {
+                        if let Some(difficulty) = hello.and_then(|hello| hello.difficulty) {
+                            messages.push(ChannelMsg::Msg(Some(ClientMessage::Request(
+                                Request::SetDifficulty(difficulty),
+                            ))));
+                        }
+                        if remote.iter().chain(&humans).filter(is_free).count() == 1 {
+                            messages.push(ChannelMsg::Start);
+                        }
+                    }
+
+                    // Saving tcp stream for future update sending
+                    conns.push((player.clone(), stream));
+                    player
                 };
 
-                // Sending player & game state info to new player
-                write_json_data(&mut stream, &player);
-                write_json_data(&mut stream, &(*game_state));
+                // Sent without the locks, the game loop takes them on receiving.
+                for msg in messages {
+                    let _ = moves_writer.send(msg);
+                }
 
-                // Starting player handler in a new thread
+                // The thread goes on handling the player's messages
                 if let Some(player) = player {
-                    let stream_clone = stream.try_clone().expect("IO error: clonning TcpStream");
-                    let chan = match player {
-                        Player::White => white_moves_writer.clone(),
-                        Player::Black => black_moves_writer.clone(),
-                    };
-                    thread::spawn(move || {
-                        handle_players_moves(player, stream_clone, chan);
-                    });
+                    handle_players_moves(player, reader, first_line, moves_writer);
                 }
-
-                // Saving tcp stream for future update sending
-                conns.push(stream);
-            }
+            });
         });
     });
 
     let game_state = game_state.clone();
     let connections = connections.clone();
 
+    let update_bots = |game_state: &GameState<R>| {
+        for (player, states) in &bots {
+            let _ = states.send(game_state.view_for(Some(player), false));
+        }
+    };
+    if started {
+        update_bots(&game_state.read().unwrap());
+    }
+
     // Main game logic:
     // 1) get moves from players
     // 2) try to apply them
@@ -138,50 +588,115 @@ fn main() {
             let game_state = game_state.read().unwrap();
 
             if game_state.is_finished() {
-                println!("Winner is {:?}", game_state.get_winner().unwrap());
+                match game_state.get_winner() {
+                    Some(winner) => println!("Winner is {:?}", winner),
+                    None => println!("Draw"),
+                }
+                if let Some(path) = &options.archive {
+                    if let Err(e) = archive(&game_state, path) {
+                        eprintln!("{}", e);
+                    }
+                }
+                if let Some(path) = &options.analysis {
+                    println!("Analyzing the game...");
+                    let engine = Engine {
+                        time_limit: ANALYSIS_TIME,
+                        threads: options.threads,
+                        weights: weights.clone(),
+                        ..Difficulty::Hard.engine()
+                    };
+                    match analyze(&game_state, &engine, path) {
+                        Ok(()) => println!("Annotated record appended to {}", path),
+                        Err(e) => eprintln!("{}", e),
+                    }
+                }
                 return;
             }
         }
 
         {
-            let apply_message = |msg: Result<ChannelMsg<Option<Move>>, crossbeam::RecvError>,
-                                 game_state: &mut GameState| {
-                if msg.is_err() {
-                    return;
+            // read move & maybe apply it
+            match moves_reader.recv() {
+                Ok(ChannelMsg::Close) => {
+                    eprintln!("Some player left the game");
+                    process::exit(1);
                 }
-                let msg = msg.unwrap();
+                Ok(ChannelMsg::Start) => started = true,
+                Ok(ChannelMsg::Msg(Some(ClientMessage::Move(mv)))) => {
+                    let mut game_state = game_state.write().unwrap();
+                    game_state.make_move(mv);
 
-                match msg {
-                    ChannelMsg::Close => {
-                        eprintln!("Some player left the game");
-                        process::exit(1);
+                    if let (true, Some(tablebase)) = (options.adjudicate, &tablebase) {
+                        adjudicate(&mut game_state, tablebase);
                     }
-                    ChannelMsg::Msg(msg) => {
-                        if let Some(mv) = msg {
-                            (*game_state).make_move(mv);
-                        }
+                }
+                Ok(ChannelMsg::Msg(Some(ClientMessage::Request(Request::SetDifficulty(
+                    difficulty,
+                ))))) => {
+                    if game_state.read().unwrap().get_history().is_empty() {
+                        println!("Engine difficulty: {}", difficulty);
+                        *engine.write().unwrap() = engine_for(&difficulty);
+                    } else {
+                        println!(
+                            "Difficulty change to {} refused, the game has started",
+                            difficulty
+                        );
                     }
                 }
-            };
+                Ok(ChannelMsg::Msg(Some(ClientMessage::Request(Request::Hint(player))))) => {
+                    let view = {
+                        let game_state = game_state.read().unwrap();
+                        let allowed =
+                            game_state.hints_left(&player) > 0 && game_state.is_to_move(&player);
+                        // Searched on the player's view, hints don't see through fog.
+                        let view = game_state.view_for(Some(&player), false);
+                        Some((view, game_state.get_history().len()))
+                            .filter(|_| allowed && !hinting.contains(&player))
+                    };
 
-            // read move & maybe apply it
-            select! {
-                recv(white_moves_reader) -> msg => {
-                    let mut game_state = game_state.write().unwrap();
-                    apply_message(msg, &mut game_state)
-                },
-                recv(black_moves_reader) -> msg => {
+                    // Searched aside like bots' moves, the game goes on meanwhile.
+                    if let Some((view, ply)) = view {
+                        hinting.push(player.clone());
+                        let engine = engine_for(&Difficulty::Hard);
+                        let chan = hints_writer.clone();
+                        thread::spawn(move || {
+                            let mv = engine.best_move(&view, &player);
+                            let _ = chan.send(ChannelMsg::Hint { player, ply, mv });
+                        });
+                    }
+                }
+                Ok(ChannelMsg::Hint { player, ply, mv }) => {
+                    hinting.retain(|asking| *asking != player);
                     let mut game_state = game_state.write().unwrap();
-                    apply_message(msg, &mut game_state)
-                },
+
+                    // Given only if the position searched is still on the board.
+                    let current = game_state.get_history().len() == ply
+                        && game_state.is_to_move(&player)
+                        && game_state.hints_left(&player) > 0;
+                    if let (true, Some(mv)) = (current, mv) {
+                        println!("Hint for {:?}: {} -> {}", player, mv.from, mv.to);
+                        game_state.give_hint(mv);
+                    }
+                }
+                _ => {}
             }
 
             {
                 let connections = connections.read().unwrap();
-                // send updates
-                for stream in (*connections).iter() {
+                let game_state = game_state.read().unwrap();
+                // send updates, each connection gets its own view of the game
+                for (player, stream) in (*connections).iter() {
                     let mut stream = stream.try_clone().expect("IO error: cloning TcpStream ");
-                    write_json_data(&mut stream, &(*game_state));
+                    let view = view_with_explorer(
+                        &game_state,
+                        player.as_ref(),
+                        options.fog_spectators,
+                        explorer.as_deref(),
+                    );
+                    write_json_data(&mut stream, &view);
+                }
+                if started {
+                    update_bots(&game_state);
                 }
             }
         }
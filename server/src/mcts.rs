@@ -0,0 +1,235 @@
+//! Monte Carlo Tree Search player: grows a search tree by random playouts
+//! instead of evaluating positions, so it needs no knowledge of the variant.
+
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::gamesate::GameState;
+use crate::rules::Ruleset;
+use crate::types::*;
+
+/// Playouts still running after this many moves are scored as draws.
+const MAX_PLAYOUT_PLIES: usize = 200;
+
+/// Search limits of the tree search.
+#[derive(Debug, Clone)]
+pub struct Mcts {
+    /// Playouts run for every move.
+    pub playouts: u32,
+    /// Weight of the UCT exploration term, higher values try rarely
+    /// visited moves more often.
+    pub exploration: f64,
+}
+
+impl Default for Mcts {
+    fn default() -> Mcts {
+        Mcts {
+            playouts: 2000,
+            exploration: std::f64::consts::SQRT_2,
+        }
+    }
+}
+
+struct Node<R: Ruleset> {
+    state: GameState<R>,
+    /// Move leading here and the player who made it, `None` for the root.
+    mv: Option<Move>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// Moves not expanded into children yet.
+    untried: Vec<Move>,
+    visits: u32,
+    /// Sum of playout results for the player who made `mv`.
+    reward: f64,
+}
+
+impl<R: Ruleset> Node<R> {
+    fn new(state: GameState<R>, mv: Option<Move>, parent: Option<usize>) -> Node<R> {
+        let untried = match state.to_move() {
+            Some(player) => state.legal_moves_for(&player),
+            None => vec![],
+        };
+
+        Node {
+            state,
+            mv,
+            parent,
+            children: vec![],
+            untried,
+            visits: 0,
+            reward: 0.0,
+        }
+    }
+}
+
+impl Mcts {
+    /// Most visited move for `player` after all playouts, `None` if it's not their turn.
+    pub fn best_move<R: Ruleset>(&self, state: &GameState<R>, player: &Player) -> Option<Move> {
+        if state.to_move().as_ref() != Some(player) {
+            return None;
+        }
+
+        let mut rng = rand::thread_rng();
+        let mut tree = vec![Node::new(state.clone(), None, None)];
+
+        for _ in 0..self.playouts {
+            let leaf = self.select(&tree);
+            let leaf = expand(&mut tree, leaf, &mut rng);
+            let result = playout(&tree[leaf].state, &mut rng);
+            backpropagate(&mut tree, leaf, &result);
+        }
+
+        tree[0]
+            .children
+            .iter()
+            .max_by_key(|&&child| tree[child].visits)
+            .and_then(|&child| tree[child].mv.clone())
+    }
+
+    /// Walks down fully expanded nodes, picking children by UCT.
+    fn select<R: Ruleset>(&self, tree: &[Node<R>]) -> usize {
+        let mut index = 0;
+
+        while tree[index].untried.is_empty() && !tree[index].children.is_empty() {
+            let parent_visits = f64::from(tree[index].visits).ln();
+
+            index = *tree[index]
+                .children
+                .iter()
+                .max_by(|&&a, &&b| {
+                    let a = self.uct(&tree[a], parent_visits);
+                    let b = self.uct(&tree[b], parent_visits);
+                    a.partial_cmp(&b).expect("UCT value is a number")
+                })
+                .expect("node has children");
+        }
+
+        index
+    }
+
+    fn uct<R: Ruleset>(&self, node: &Node<R>, ln_parent_visits: f64) -> f64 {
+        let visits = f64::from(node.visits);
+        node.reward / visits + self.exploration * (ln_parent_visits / visits).sqrt()
+    }
+}
+
+/// Adds a child for a random untried move, returns the node to play out from.
+fn expand<R: Ruleset>(tree: &mut Vec<Node<R>>, index: usize, rng: &mut impl Rng) -> usize {
+    if tree[index].untried.is_empty() {
+        return index;
+    }
+
+    let pick = rng.gen_range(0, tree[index].untried.len());
+    let mv = tree[index].untried.swap_remove(pick);
+    let mut state = tree[index].state.clone();
+    state.make_move(mv.clone());
+
+    tree.push(Node::new(state, Some(mv), Some(index)));
+    let child = tree.len() - 1;
+    tree[index].children.push(child);
+
+    child
+}
+
+/// Plays random moves until the game ends, returns the finished game
+/// (or the last state if the game went on for too long).
+fn playout<R: Ruleset>(state: &GameState<R>, rng: &mut impl Rng) -> GameState<R> {
+    let mut state = state.clone();
+
+    for _ in 0..MAX_PLAYOUT_PLIES {
+        let player = match state.to_move() {
+            Some(player) => player,
+            None => break,
+        };
+
+        match state.legal_moves_for(&player).choose(rng) {
+            Some(mv) => state.make_move(mv.clone()),
+            None => break,
+        }
+    }
+
+    state
+}
+
+/// Adds the playout result to every node on the way to the root.
+fn backpropagate<R: Ruleset>(tree: &mut [Node<R>], leaf: usize, result: &GameState<R>) {
+    let mut index = Some(leaf);
+
+    while let Some(current) = index {
+        let node = &mut tree[current];
+        node.visits += 1;
+
+        if let Some(mv) = &node.mv {
+            node.reward += match result.get_winner() {
+                Some(winner) if result.allies(&winner, &mv.player) => 1.0,
+                Some(_) => 0.0,
+                None => 0.5,
+            };
+        }
+
+        index = node.parent;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rules::Classic;
+    use crate::victory::Victory;
+
+    fn mv(player: Player, from: (u8, u8), to: (u8, u8)) -> Move {
+        Move {
+            player,
+            from: Position {
+                x: from.0,
+                y: from.1,
+            },
+            to: Position { x: to.0, y: to.1 },
+        }
+    }
+
+    #[test]
+    fn finds_the_winning_capture() {
+        let mcts = Mcts {
+            playouts: 300,
+            ..Mcts::default()
+        };
+        let mut state = GameState::<Classic>::new(8, 8).with_victory(Victory::Captures(1));
+        state.make_move(mv(Player::White, (6, 6), (5, 4)));
+        state.make_move(mv(Player::Black, (1, 1), (2, 3)));
+        state.make_move(mv(Player::White, (5, 4), (3, 5)));
+        // Black's only capture, and the first capture wins.
+        let capture = mv(Player::Black, (2, 3), (3, 5));
+
+        for _ in 0..3 {
+            assert_eq!(
+                mcts.best_move(&state, &Player::Black),
+                Some(capture.clone())
+            );
+        }
+        assert_eq!(mcts.best_move(&state, &Player::White), None);
+    }
+
+    #[test]
+    fn plays_only_legal_moves() {
+        let mcts = Mcts {
+            playouts: 50,
+            ..Mcts::default()
+        };
+        let mut state = GameState::<Classic>::new(8, 8).with_victory(Victory::Captures(3));
+
+        for _ in 0..10 {
+            let player = match state.to_move() {
+                Some(player) if !state.is_finished() => player,
+                _ => break,
+            };
+            let mv = mcts.best_move(&state, &player).unwrap();
+            assert!(
+                state.legal_moves_for(&player).contains(&mv),
+                "{:?} is not legal",
+                mv
+            );
+            state.make_move(mv);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+//! Text notation for squares, as shown by the client:
+//! column letter followed by row number counted from the top, e.g. "c3".
+//! Players are named in lowercase, e.g. "white".
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::types::{Player, Position};
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", char::from(b'a' + self.x), self.y)
+    }
+}
+
+impl FromStr for Position {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Position, String> {
+        let mut chars = s.chars();
+        let x = match chars.next() {
+            Some(letter @ 'a'..='z') => letter as u8 - b'a',
+            _ => return Err(format!("'{}' is not a square", s)),
+        };
+        let y = chars
+            .as_str()
+            .parse()
+            .map_err(|_| format!("'{}' is not a square", s))?;
+
+        Ok(Position { x, y })
+    }
+}
+
+impl FromStr for Player {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Player, String> {
+        match s {
+            "white" => Ok(Player::White),
+            "black" => Ok(Player::Black),
+            "red" => Ok(Player::Red),
+            "blue" => Ok(Player::Blue),
+            _ => Err(format!(
+                "Unknown player '{}', expected one of: white, black, red, blue",
+                s
+            )),
+        }
+    }
+}
+
+/// Parses comma separated list of squares, e.g. "c3,f4".
+pub fn parse_squares(s: &str) -> Result<Vec<Position>, String> {
+    s.split(',')
+        .filter(|square| !square.is_empty())
+        .map(str::parse)
+        .collect()
+}
@@ -0,0 +1,247 @@
+use crate::engine::Difficulty;
+use crate::handicap::Handicap;
+use crate::mcts::Mcts;
+use crate::notation::parse_squares;
+use crate::rules::VARIANTS;
+use crate::types::{Player, Position, MAX_BOARD_SIZE, MIN_BOARD_SIZE};
+use crate::victory::Victory;
+
+pub const USAGE: &str = "Usage: horse-server [--width N] [--height N] [--variant NAME] \
+                         [--victory CONDITION] [--obstacles SQUARES] [--fog-spectators] \
+                         [--handicap HANDICAP]... [--bot PLAYER[:KIND]]... \
+                         [--difficulty LEVEL] [--threads N] [--ponder] [--playouts N] \
+                         [--exploration C] [--tablebase FILE] [--adjudicate] [--book FILE] \
+                         [--explorer] [--archive FILE] [--eval FILE] [--analysis FILE] \
+                         [--hints N]";
+
+/// Algorithm playing an engine seat.
+#[derive(Debug, PartialEq, Clone)]
+pub enum BotKind {
+    /// Alpha-beta search, see `engine`.
+    AlphaBeta,
+    /// Monte Carlo Tree Search, see `mcts`.
+    Mcts,
+    /// Program started with the given command, see `external`.
+    External(String),
+    /// Program connecting over TCP and introducing itself as a bot.
+    Remote,
+}
+
+/// Seat taken by an engine.
+#[derive(Debug, Clone)]
+pub struct Bot {
+    pub player: Player,
+    pub kind: BotKind,
+}
+
+/// Game parameters given on the command line.
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Board size, the variant's default unless given.
+    pub width: Option<u8>,
+    pub height: Option<u8>,
+    pub variant: String,
+    /// Overrides the variant's default victory condition.
+    pub victory: Option<Victory>,
+    /// Squares blocked in addition to the variant's ones.
+    pub obstacles: Vec<Position>,
+    /// In fog games spectators see only squares seen by both players.
+    pub fog_spectators: bool,
+    /// Odds given to weaker players, the flag may be repeated.
+    pub handicaps: Vec<Handicap>,
+    /// Seats taken by engines, the flag may be repeated.
+    pub bots: Vec<Bot>,
+    /// Strength of the alpha-beta engine, players may change it during the game.
+    pub difficulty: Difficulty,
+    /// Search threads of the alpha-beta engine.
+    pub threads: usize,
+    /// The alpha-beta engine keeps searching while its opponents think.
+    pub ponder: bool,
+    /// Limits of the tree search engine.
+    pub mcts: Mcts,
+    /// Endgame tablebase file used by the alpha-beta engine.
+    pub tablebase: Option<String>,
+    /// Games reaching a position the tablebase covers end with its result.
+    pub adjudicate: bool,
+    /// Opening book file used by the alpha-beta engine.
+    pub book: Option<String>,
+    /// Views sent to clients list the book's moves from the position.
+    pub explorer: bool,
+    /// File finished games are appended to, one JSON game state per line.
+    pub archive: Option<String>,
+    /// Evaluation weights of the alpha-beta engine, as written by `horse-tune`.
+    pub eval: Option<String>,
+    /// File finished games are appended to with the engine's annotations.
+    pub analysis: Option<String>,
+    /// Hints each player may ask for, none when 0.
+    pub hints: u8,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            width: None,
+            height: None,
+            variant: "classic".to_string(),
+            victory: None,
+            obstacles: vec![],
+            fog_spectators: false,
+            handicaps: vec![],
+            bots: vec![],
+            difficulty: Difficulty::Hard,
+            threads: 1,
+            ponder: false,
+            mcts: Mcts::default(),
+            tablebase: None,
+            adjudicate: false,
+            book: None,
+            explorer: false,
+            archive: None,
+            eval: None,
+            analysis: None,
+            hints: 0,
+        }
+    }
+}
+
+/// Parses a board side, checking that it is in the supported range.
+pub fn parse_size(flag: &str, value: Option<String>) -> Result<u8, String> {
+    let value = value.ok_or_else(|| format!("{} requires a value", flag))?;
+    let size: u8 = value
+        .parse()
+        .map_err(|_| format!("{}: '{}' is not a number", flag, value))?;
+
+    if !(MIN_BOARD_SIZE..=MAX_BOARD_SIZE).contains(&size) {
+        return Err(format!(
+            "{}: board side must be between {} and {}",
+            flag, MIN_BOARD_SIZE, MAX_BOARD_SIZE
+        ));
+    }
+
+    Ok(size)
+}
+
+/// Parses an engine seat: "PLAYER" or "PLAYER:KIND", KIND is alpha-beta (default),
+/// mcts, remote or external:COMMAND.
+fn parse_bot(value: Option<String>) -> Result<Bot, String> {
+    let value = value.ok_or("--bot requires a value")?;
+    let mut parts = value.splitn(3, ':');
+    let player = parts.next().unwrap_or("").parse()?;
+    let kind = match (parts.next(), parts.next()) {
+        (None, _) | (Some("alpha-beta"), None) => BotKind::AlphaBeta,
+        (Some("mcts"), None) => BotKind::Mcts,
+        (Some("remote"), None) => BotKind::Remote,
+        (Some("external"), Some(command)) if !command.trim().is_empty() => {
+            BotKind::External(command.to_string())
+        }
+        (Some("external"), _) => return Err("external engine requires a command".to_string()),
+        _ => {
+            return Err(format!(
+                "Unknown engine '{}', expected alpha-beta, mcts, remote or external:COMMAND",
+                value
+            ))
+        }
+    };
+
+    Ok(Bot { player, kind })
+}
+
+/// Parses a variant name, checking that such variant exists.
+fn parse_variant(value: Option<String>) -> Result<String, String> {
+    let value = value.ok_or("--variant requires a value")?;
+
+    if !VARIANTS.contains(&value.as_str()) {
+        return Err(format!(
+            "Unknown variant '{}', expected one of: {}",
+            value,
+            VARIANTS.join(", ")
+        ));
+    }
+
+    Ok(value)
+}
+
+/// Parses options from command line arguments (without program name).
+pub fn parse_options(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--width" => options.width = Some(parse_size(&arg, args.next())?),
+            "--height" => options.height = Some(parse_size(&arg, args.next())?),
+            "--variant" => options.variant = parse_variant(args.next())?,
+            "--victory" => {
+                let value = args.next().ok_or("--victory requires a value")?;
+                options.victory = Some(value.parse()?);
+            }
+            "--obstacles" => {
+                let value = args.next().ok_or("--obstacles requires a value")?;
+                options.obstacles = parse_squares(&value)?;
+            }
+            "--fog-spectators" => options.fog_spectators = true,
+            "--handicap" => {
+                let value = args.next().ok_or("--handicap requires a value")?;
+                options.handicaps.push(value.parse()?);
+            }
+            "--bot" => options.bots.push(parse_bot(args.next())?),
+            "--difficulty" => {
+                let value = args.next().ok_or("--difficulty requires a value")?;
+                options.difficulty = value.parse()?;
+            }
+            "--threads" => {
+                let value = args.next().ok_or("--threads requires a value")?;
+                options.threads = value
+                    .parse()
+                    .ok()
+                    .filter(|&threads| threads > 0)
+                    .ok_or_else(|| format!("'{}' is not a positive number", value))?;
+            }
+            "--ponder" => options.ponder = true,
+            "--playouts" => {
+                let value = args.next().ok_or("--playouts requires a value")?;
+                options.mcts.playouts = value
+                    .parse()
+                    .ok()
+                    .filter(|&playouts| playouts > 0)
+                    .ok_or_else(|| format!("'{}' is not a positive number", value))?;
+            }
+            "--exploration" => {
+                let value = args.next().ok_or("--exploration requires a value")?;
+                options.mcts.exploration = value
+                    .parse()
+                    .ok()
+                    .filter(|&exploration: &f64| exploration >= 0.0)
+                    .ok_or_else(|| format!("'{}' is not a non-negative number", value))?;
+            }
+            "--tablebase" => {
+                options.tablebase = Some(args.next().ok_or("--tablebase requires a value")?);
+            }
+            "--adjudicate" => options.adjudicate = true,
+            "--book" => options.book = Some(args.next().ok_or("--book requires a value")?),
+            "--explorer" => options.explorer = true,
+            "--archive" => {
+                options.archive = Some(args.next().ok_or("--archive requires a value")?);
+            }
+            "--eval" => options.eval = Some(args.next().ok_or("--eval requires a value")?),
+            "--analysis" => {
+                options.analysis = Some(args.next().ok_or("--analysis requires a value")?);
+            }
+            "--hints" => {
+                let value = args.next().ok_or("--hints requires a value")?;
+                options.hints = value
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a number of hints", value))?;
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    if options.adjudicate && options.tablebase.is_none() {
+        return Err(String::from("--adjudicate requires --tablebase"));
+    }
+    if options.explorer && options.book.is_none() {
+        return Err(String::from("--explorer requires --book"));
+    }
+
+    Ok(options)
+}
@@ -0,0 +1,38 @@
+//! Messages clients send to the server, one JSON value per line.
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::Difficulty;
+use crate::types::{Move, Player};
+
+/// Line sent by a client: a move, or a request about the game.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ClientMessage {
+    Move(Move),
+    Request(Request),
+}
+
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Request {
+    /// Sets the strength of engine players in the game, before the first
+    /// move.
+    SetDifficulty(Difficulty),
+    /// Introduces the client, see `Hello`.
+    Hello(Hello),
+    /// Asks for the engine's move for the player, in games with hints.
+    Hint(Player),
+}
+
+/// Optional first line of a connection, sent before the server answers
+/// with the seat. Clients that don't send it are seated as humans.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct Hello {
+    pub name: String,
+    /// The client is a program playing on its own.
+    #[serde(default)]
+    pub bot: bool,
+    /// Strength asked for, as with `Request::SetDifficulty`.
+    #[serde(default)]
+    pub difficulty: Option<Difficulty>,
+}
@@ -0,0 +1,455 @@
+use std::fmt::Debug;
+
+use crate::gamesate::GameState;
+use crate::types::*;
+use crate::victory::Victory;
+
+/// Names of all shipped variants, accepted by `--variant`.
+pub const VARIANTS: &[&str] = &[
+    Classic::NAME,
+    Crowded::NAME,
+    Blockade::NAME,
+    Giveaway::NAME,
+    Menagerie::NAME,
+    Craters::NAME,
+    Torus::NAME,
+    Fog::NAME,
+    Simultaneous::NAME,
+    FourPlayer::NAME,
+    Teams::NAME,
+];
+
+/// Calls `$run`, a function generic over the ruleset, with the variant
+/// named `$name`: `with_variant!(name, run_game(&options))`. The name must
+/// be one of `VARIANTS`.
+#[macro_export]
+macro_rules! with_variant {
+    ($name:expr, $run:ident($($arg:expr),*)) => {
+        match $name {
+            <$crate::rules::Classic as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::Classic>($($arg),*)
+            }
+            <$crate::rules::Crowded as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::Crowded>($($arg),*)
+            }
+            <$crate::rules::Blockade as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::Blockade>($($arg),*)
+            }
+            <$crate::rules::Giveaway as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::Giveaway>($($arg),*)
+            }
+            <$crate::rules::Menagerie as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::Menagerie>($($arg),*)
+            }
+            <$crate::rules::Craters as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::Craters>($($arg),*)
+            }
+            <$crate::rules::Torus as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::Torus>($($arg),*)
+            }
+            <$crate::rules::Fog as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::Fog>($($arg),*)
+            }
+            <$crate::rules::Simultaneous as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::Simultaneous>($($arg),*)
+            }
+            <$crate::rules::FourPlayer as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::FourPlayer>($($arg),*)
+            }
+            <$crate::rules::Teams as $crate::rules::Ruleset>::NAME => {
+                $run::<$crate::rules::Teams>($($arg),*)
+            }
+            name => unreachable!("unknown variant '{}'", name),
+        }
+    };
+}
+
+/// Checks that variant `R` can be played on a `width` x `height` board.
+pub fn check_size<R: Ruleset>(width: u8, height: u8) -> Result<(), String> {
+    let (min_width, min_height) = R::min_size();
+
+    if width > MAX_BOARD_SIZE || height > MAX_BOARD_SIZE {
+        return Err(format!("Board sides can't exceed {}", MAX_BOARD_SIZE));
+    }
+    if width < min_width || height < min_height {
+        return Err(format!(
+            "{} needs a board of at least {}x{}",
+            R::NAME,
+            min_width,
+            min_height
+        ));
+    }
+
+    Ok(())
+}
+
+/// Board with pieces on the two home rows: `kind_at(x, y)` gives kind of the
+/// piece standing on (x, y), if any. `outer` tells whether y is the edge row.
+/// Black is at the top (rows 0 and 1), White at the bottom.
+pub fn home_rows(width: u8, height: u8, kind_at: fn(usize, usize, bool) -> Option<u8>) -> Board {
+    let mut board = vec![vec![Piece::Empty; width as usize]; height as usize];
+    let last = height as usize - 1;
+
+    for (y, row) in board.iter_mut().enumerate() {
+        let owner = match y {
+            0 | 1 => Player::Black,
+            y if y + 1 >= last => Player::White,
+            _ => continue,
+        };
+        let outer = y == 0 || y == last;
+
+        for (x, piece) in row.iter_mut().enumerate() {
+            if let Some(kind) = kind_at(x, y, outer) {
+                *piece = Piece::Leaper {
+                    kind,
+                    owner: owner.clone(),
+                };
+            }
+        }
+    }
+
+    board
+}
+
+/// Four-player board: each player has two rows along their own edge,
+/// 2x2 corners (blocked by `corners`) are left out. Black is at the top,
+/// White at the bottom, Red on the left and Blue on the right.
+pub fn four_sides(width: u8, height: u8, kind_at: fn(usize, usize, bool) -> Option<u8>) -> Board {
+    let (width, height) = (width as usize, height as usize);
+    let mut board = vec![vec![Piece::Empty; width]; height];
+
+    for (y, row) in board.iter_mut().enumerate() {
+        for (x, piece) in row.iter_mut().enumerate() {
+            let (owner, outer) = match (x, y) {
+                (x, y) if (x < 2 || x + 2 >= width) && (y < 2 || y + 2 >= height) => continue,
+                (_, 0 | 1) => (Player::Black, y == 0),
+                (_, y) if y + 2 >= height => (Player::White, y + 1 == height),
+                (0 | 1, _) => (Player::Red, x == 0),
+                (x, _) if x + 2 >= width => (Player::Blue, x + 1 == width),
+                _ => continue,
+            };
+
+            if let Some(kind) = kind_at(x, y, outer) {
+                *piece = Piece::Leaper { kind, owner };
+            }
+        }
+    }
+
+    board
+}
+
+/// 2x2 squares in every corner of the board.
+pub fn corners(width: u8, height: u8) -> Vec<Position> {
+    let mut squares = vec![];
+
+    for y in [0, 1, height - 2, height - 1] {
+        for x in [0, 1, width - 2, width - 1] {
+            squares.push(Position { x, y });
+        }
+    }
+
+    squares
+}
+
+/// Every other square of home rows is taken by a knight.
+fn checkered_knights(x: usize, y: usize, _outer: bool) -> Option<u8> {
+    if (x + y).is_multiple_of(2) {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+/// Rules of a game variant.
+/// Every method has a default matching the classic game, so variants
+/// only override what they change.
+pub trait Ruleset: Debug + PartialEq + Clone + Default + Send + Sync + 'static {
+    /// Name used to select the variant.
+    const NAME: &'static str;
+
+    /// Board size used unless another is chosen for the game.
+    fn default_size() -> (u8, u8) {
+        (8, 8)
+    }
+
+    /// Smallest board giving every player their whole starting army.
+    fn min_size() -> (u8, u8) {
+        (MIN_BOARD_SIZE, MIN_BOARD_SIZE)
+    }
+
+    /// Seats in turn order.
+    fn players() -> Vec<Player> {
+        PLAYERS.to_vec()
+    }
+
+    /// Players winning together, by default everyone plays for themselves.
+    fn teams() -> Vec<Vec<Player>> {
+        Self::players()
+            .into_iter()
+            .map(|player| vec![player])
+            .collect()
+    }
+
+    /// Victory condition used unless another is chosen for the game.
+    fn default_victory() -> Victory {
+        Victory::MinPieces(4)
+    }
+
+    /// Kinds of pieces in the game, `Piece::Leaper` refers to them by index.
+    fn kinds() -> Vec<PieceKind> {
+        vec![PieceKind::knight()]
+    }
+
+    /// Starting position: knights on every other square of the two home rows.
+    fn setup(width: u8, height: u8) -> Board {
+        home_rows(width, height, checkered_knights)
+    }
+
+    /// How board edges are connected.
+    fn topology() -> Topology {
+        Topology::Bounded
+    }
+
+    /// Whether players see only squares their pieces occupy or attack.
+    fn fog() -> bool {
+        false
+    }
+
+    /// Whether players move at the same time, see `GameState::resolve_turn`.
+    fn simultaneous() -> bool {
+        false
+    }
+
+    /// Squares blocked from the start.
+    fn obstacles(_width: u8, _height: u8) -> Vec<Position> {
+        vec![]
+    }
+
+    /// Whether `piece` may move onto square holding `target`.
+    fn can_capture(piece: &Piece, target: &Piece) -> bool {
+        piece.owner() != target.owner()
+    }
+
+    /// Whether player having a capture must take something.
+    fn compulsory_captures() -> bool {
+        false
+    }
+
+    /// Winner of the position, checked after every move.
+    fn winner(state: &GameState<Self>) -> Option<Player> {
+        state.victory_winner()
+    }
+}
+
+/// The original game: knights, captures, fewer than 4 knights loses.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Classic;
+
+impl Ruleset for Classic {
+    const NAME: &'static str = "classic";
+}
+
+/// Both home rows are full, fewer than 8 knights loses.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Crowded;
+
+impl Ruleset for Crowded {
+    const NAME: &'static str = "crowded";
+
+    fn default_victory() -> Victory {
+        Victory::MinPieces(8)
+    }
+
+    fn setup(width: u8, height: u8) -> Board {
+        home_rows(width, height, |_, _, _| Some(0))
+    }
+}
+
+/// Knights can't capture, player left without moves loses.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Blockade;
+
+impl Ruleset for Blockade {
+    const NAME: &'static str = "blockade";
+
+    fn default_victory() -> Victory {
+        Victory::Immobilize
+    }
+
+    fn can_capture(_piece: &Piece, target: &Piece) -> bool {
+        *target == Piece::Empty
+    }
+}
+
+/// Captures are compulsory, fulfilling the victory condition loses:
+/// by default first player left with fewer than 4 knights, or without moves, wins.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Giveaway;
+
+impl Ruleset for Giveaway {
+    const NAME: &'static str = "giveaway";
+
+    fn compulsory_captures() -> bool {
+        true
+    }
+
+    fn winner(state: &GameState<Self>) -> Option<Player> {
+        state.victory_winner().map(|player| player.opponent())
+    }
+}
+
+/// Knights are joined by fairy leapers: camels and zebras on the edge row,
+/// ferzes and wazirs between the knights.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Menagerie;
+
+impl Ruleset for Menagerie {
+    const NAME: &'static str = "menagerie";
+
+    fn kinds() -> Vec<PieceKind> {
+        vec![
+            PieceKind::knight(),
+            PieceKind::camel(),
+            PieceKind::zebra(),
+            PieceKind::ferz(),
+            PieceKind::wazir(),
+        ]
+    }
+
+    fn setup(width: u8, height: u8) -> Board {
+        home_rows(width, height, |x, y, outer| {
+            match (outer, (x + y).is_multiple_of(2), x % 4 < 2) {
+                (true, true, true) => Some(1),
+                (true, true, false) => Some(2),
+                (false, true, _) => Some(0),
+                (false, false, true) => Some(3),
+                (false, false, false) => Some(4),
+                _ => None,
+            }
+        })
+    }
+}
+
+/// Four blocked squares in the middle of the board.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Craters;
+
+impl Ruleset for Craters {
+    const NAME: &'static str = "craters";
+
+    fn obstacles(width: u8, height: u8) -> Vec<Position> {
+        let (left, right) = (width / 4, width - 1 - width / 4);
+        let (top, bottom) = ((height - 1) / 2, height / 2);
+
+        vec![
+            Position { x: left, y: top },
+            Position { x: right, y: top },
+            Position { x: left, y: bottom },
+            Position {
+                x: right,
+                y: bottom,
+            },
+        ]
+    }
+}
+
+/// Board edges wrap around, so edge squares are as strong as center ones.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Torus;
+
+impl Ruleset for Torus {
+    const NAME: &'static str = "torus";
+
+    fn topology() -> Topology {
+        Topology::Toroidal
+    }
+}
+
+/// Fog of war: each player sees only squares their knights occupy or attack.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Fog;
+
+impl Ruleset for Fog {
+    const NAME: &'static str = "fog";
+
+    fn fog() -> bool {
+        true
+    }
+}
+
+/// Both players submit a move each turn, moves are resolved together.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Simultaneous;
+
+impl Ruleset for Simultaneous {
+    const NAME: &'static str = "simultaneous";
+
+    fn simultaneous() -> bool {
+        true
+    }
+}
+
+/// Four players on a 10x10 board, each for themselves.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct FourPlayer;
+
+impl Ruleset for FourPlayer {
+    const NAME: &'static str = "four-player";
+
+    fn default_size() -> (u8, u8) {
+        (10, 10)
+    }
+
+    /// Sides between the corners are at least 4 squares long.
+    fn min_size() -> (u8, u8) {
+        (8, 8)
+    }
+
+    fn players() -> Vec<Player> {
+        vec![Player::White, Player::Red, Player::Black, Player::Blue]
+    }
+
+    fn setup(width: u8, height: u8) -> Board {
+        four_sides(width, height, checkered_knights)
+    }
+
+    fn obstacles(width: u8, height: u8) -> Vec<Position> {
+        corners(width, height)
+    }
+}
+
+/// Four players in two teams: White and Black against Red and Blue.
+/// Partners sit across the board and can't capture each other.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Teams;
+
+impl Ruleset for Teams {
+    const NAME: &'static str = "teams";
+
+    fn default_size() -> (u8, u8) {
+        FourPlayer::default_size()
+    }
+
+    fn min_size() -> (u8, u8) {
+        FourPlayer::min_size()
+    }
+
+    fn players() -> Vec<Player> {
+        FourPlayer::players()
+    }
+
+    fn teams() -> Vec<Vec<Player>> {
+        vec![
+            vec![Player::White, Player::Black],
+            vec![Player::Red, Player::Blue],
+        ]
+    }
+
+    fn setup(width: u8, height: u8) -> Board {
+        FourPlayer::setup(width, height)
+    }
+
+    fn obstacles(width: u8, height: u8) -> Vec<Position> {
+        FourPlayer::obstacles(width, height)
+    }
+}
@@ -0,0 +1,817 @@
+//! Endgame tablebases for knights on a bounded board without obstacles,
+//! where a side left with fewer than `min_pieces` knights or without moves
+//! loses (the classic variant).
+//!
+//! Tables are generated by retrograde analysis and store one byte per
+//! position: 0 for a draw, otherwise the number of plies to the end of the
+//! game with best play plus one. Turns alternate, so odd distances are wins
+//! and even distances losses for the side to move.
+
+use std::fmt;
+use std::fs;
+
+use crate::gamesate::GameState;
+use crate::rules::Ruleset;
+use crate::victory::Victory;
+
+/// Identifies tablebase files.
+const MAGIC: &[u8; 4] = b"HTB1";
+/// Tables are indexed by `u32`-sized positions at most.
+const MAX_POSITIONS: u64 = 1 << 32;
+/// Largest distance a byte can hold.
+const MAX_DISTANCE: u8 = 254;
+
+/// Knights of White and Black.
+pub type Material = (u8, u8);
+
+/// Result of a position for the side to move, in plies to the end of the game.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Value {
+    Win(u8),
+    Loss(u8),
+    Draw,
+}
+
+impl Value {
+    fn encode(self) -> u8 {
+        match self {
+            Value::Win(distance) | Value::Loss(distance) => distance + 1,
+            Value::Draw => 0,
+        }
+    }
+
+    fn decode(byte: u8) -> Value {
+        match byte {
+            0 => Value::Draw,
+            byte if byte.is_multiple_of(2) => Value::Win(byte - 1),
+            byte => Value::Loss(byte - 1),
+        }
+    }
+}
+
+/// Knights on the board, one bit per square `x + y * width`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Knights {
+    pub white: u64,
+    pub black: u64,
+    pub white_to_move: bool,
+}
+
+impl Knights {
+    fn material(&self) -> Material {
+        (self.white.count_ones() as u8, self.black.count_ones() as u8)
+    }
+}
+
+/// Tables for every material up to the one generated.
+pub struct Tablebase {
+    width: u8,
+    height: u8,
+    min_pieces: u8,
+    /// Squares a knight attacks from each square.
+    attacks: Vec<u64>,
+    /// `binomial[n][k]`, n up to the number of squares.
+    binomial: Vec<Vec<u64>>,
+    /// Tables with White having at most as many knights as Black, the
+    /// others are looked up with colours swapped.
+    tables: Vec<(Material, Vec<u8>)>,
+}
+
+impl Tablebase {
+    fn new(width: u8, height: u8, min_pieces: u8) -> Result<Tablebase, String> {
+        let squares = usize::from(width) * usize::from(height);
+        if squares > 64 {
+            return Err(String::from(
+                "Tablebases need a board of at most 64 squares",
+            ));
+        }
+        if min_pieces == 0 {
+            return Err(String::from("Minimum number of knights must be positive"));
+        }
+
+        let mut attacks = vec![0; squares];
+        for (square, targets) in attacks.iter_mut().enumerate() {
+            let (x, y) = (
+                (square % usize::from(width)) as i32,
+                (square / usize::from(width)) as i32,
+            );
+
+            for (dx, dy) in &[
+                (1, 2),
+                (2, 1),
+                (2, -1),
+                (1, -2),
+                (-1, -2),
+                (-2, -1),
+                (-2, 1),
+                (-1, 2),
+            ] {
+                let (tx, ty) = (x + dx, y + dy);
+                if tx >= 0 && ty >= 0 && tx < i32::from(width) && ty < i32::from(height) {
+                    *targets |= 1 << (tx + ty * i32::from(width));
+                }
+            }
+        }
+
+        let mut binomial = vec![vec![0u64; squares + 1]; squares + 1];
+        for n in 0..=squares {
+            binomial[n][0] = 1;
+            for k in 1..=n {
+                binomial[n][k] = binomial[n - 1][k - 1] + binomial[n - 1][k];
+            }
+        }
+
+        Ok(Tablebase {
+            width,
+            height,
+            min_pieces,
+            attacks,
+            binomial,
+            tables: vec![],
+        })
+    }
+
+    /// Generates tables for `material` and every material reachable from it
+    /// by captures, reporting each table before it's solved.
+    pub fn generate(
+        width: u8,
+        height: u8,
+        min_pieces: u8,
+        material: Material,
+        mut progress: impl FnMut(Material, u64),
+    ) -> Result<Tablebase, String> {
+        let mut tablebase = Tablebase::new(width, height, min_pieces)?;
+        let (white, black) = canonical(material);
+
+        if white < min_pieces {
+            return Err(format!("{}v{} is already decided", material.0, material.1));
+        }
+        if usize::from(white) + usize::from(black) > tablebase.attacks.len() {
+            return Err(format!("{}v{} doesn't fit on the board", white, black));
+        }
+
+        // Fewer knights first, every capture leads to a table solved before.
+        let mut materials = vec![];
+        for w in min_pieces..=white {
+            for b in w..=black {
+                materials.push((w, b));
+            }
+        }
+        materials.sort_by_key(|(w, b)| w + b);
+
+        for &material in &materials {
+            if tablebase.len(material) > MAX_POSITIONS {
+                return Err(format!(
+                    "{}v{} on {}x{} has {} positions, at most {} are supported",
+                    material.0,
+                    material.1,
+                    width,
+                    height,
+                    tablebase.len(material),
+                    MAX_POSITIONS
+                ));
+            }
+        }
+
+        for material in materials {
+            progress(material, tablebase.len(material));
+            let values = tablebase.solve(material)?;
+            tablebase.tables.push((material, values));
+        }
+
+        Ok(tablebase)
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let mut data = MAGIC.to_vec();
+        data.extend_from_slice(&[self.width, self.height, self.min_pieces]);
+        data.push(self.tables.len() as u8);
+
+        for ((white, black), values) in &self.tables {
+            data.extend_from_slice(&[*white, *black]);
+            data.extend_from_slice(values);
+        }
+
+        fs::write(path, data).map_err(|e| format!("{}: {}", path, e))
+    }
+
+    pub fn load(path: &str) -> Result<Tablebase, String> {
+        let data = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        let corrupt = || format!("{}: not a tablebase", path);
+
+        if data.len() < 8 || &data[..4] != MAGIC {
+            return Err(corrupt());
+        }
+
+        let mut tablebase = Tablebase::new(data[4], data[5], data[6])?;
+        let mut rest = &data[8..];
+
+        for _ in 0..data[7] {
+            if rest.len() < 2 {
+                return Err(corrupt());
+            }
+            let material = (rest[0], rest[1]);
+            let len = tablebase.len(material) as usize;
+            if rest.len() < 2 + len {
+                return Err(corrupt());
+            }
+
+            tablebase.tables.push((material, rest[2..2 + len].to_vec()));
+            rest = &rest[2 + len..];
+        }
+
+        Ok(tablebase)
+    }
+
+    /// Board width and height.
+    pub fn size(&self) -> (u8, u8) {
+        (self.width, self.height)
+    }
+
+    pub fn min_pieces(&self) -> u8 {
+        self.min_pieces
+    }
+
+    /// Materials with a table, White having at most as many knights as Black.
+    pub fn materials(&self) -> impl Iterator<Item = Material> + '_ {
+        self.tables.iter().map(|(material, _)| *material)
+    }
+
+    /// Value of `state` for the player to move, `None` if the tablebase
+    /// doesn't cover it.
+    pub fn probe_state<R: Ruleset>(&self, state: &GameState<R>) -> Option<Value> {
+        if state.size() != self.size()
+            || *state.get_victory() != Victory::MinPieces(self.min_pieces)
+        {
+            return None;
+        }
+
+        self.probe(&state.knights()?)
+    }
+
+    /// Value of `knights` for the side to move, `None` without a table for it.
+    pub fn probe(&self, knights: &Knights) -> Option<Value> {
+        let (white, black) = knights.material();
+        let knights = if white > black {
+            self.mirror(knights)
+        } else {
+            *knights
+        };
+
+        let material = knights.material();
+        let (_, values) = self.tables.iter().find(|(m, _)| *m == material)?;
+
+        Some(Value::decode(values[self.index(&knights) as usize]))
+    }
+
+    /// Positions with `material`, both sides to move.
+    fn len(&self, (white, black): Material) -> u64 {
+        let squares = self.attacks.len();
+        let (white, black) = (usize::from(white), usize::from(black));
+
+        if white + black > squares {
+            return 0;
+        }
+        self.binomial[squares][white] * self.binomial[squares - white][black] * 2
+    }
+
+    fn index(&self, knights: &Knights) -> u64 {
+        let (_, black) = knights.material();
+        let squares = self.attacks.len();
+        let white_rank = self.rank(knights.white, 0);
+        let black_rank = self.rank(knights.black, knights.white);
+        let free = squares - knights.white.count_ones() as usize;
+        let side = if knights.white_to_move { 0 } else { 1 };
+
+        (white_rank * self.binomial[free][usize::from(black)] + black_rank) * 2 + side
+    }
+
+    fn unindex(&self, (white, black): Material, index: u64) -> Knights {
+        let squares = self.attacks.len();
+        let free = squares - usize::from(white);
+        let white_to_move = index.is_multiple_of(2);
+        let index = index / 2;
+        let per_white = self.binomial[free][usize::from(black)];
+
+        let white_bits = self.unrank(index / per_white, white, 0);
+        let black_bits = self.unrank(index % per_white, black, white_bits);
+
+        Knights {
+            white: white_bits,
+            black: black_bits,
+            white_to_move,
+        }
+    }
+
+    /// Rank of the set `bits` among sets of the same size, counting only
+    /// squares not in `skip`.
+    fn rank(&self, bits: u64, skip: u64) -> u64 {
+        let mut rank = 0;
+        let mut rest = bits;
+        let mut count = 1;
+
+        while rest != 0 {
+            let square = rest.trailing_zeros();
+            let below = (1u64 << square) - 1;
+            let compressed = square - (skip & below).count_ones();
+            rank += self.binomial[compressed as usize][count];
+            count += 1;
+            rest &= rest - 1;
+        }
+
+        rank
+    }
+
+    fn unrank(&self, mut rank: u64, count: u8, skip: u64) -> u64 {
+        let free = self.attacks.len() - skip.count_ones() as usize;
+        let mut bits = 0;
+        let mut limit = free;
+
+        for count in (1..=usize::from(count)).rev() {
+            let mut compressed = limit - 1;
+            while self.binomial[compressed][count] > rank {
+                compressed -= 1;
+            }
+            rank -= self.binomial[compressed][count];
+            limit = compressed;
+
+            // The compressed-th square not in `skip`.
+            let mut square = 0;
+            let mut seen = 0;
+            loop {
+                if skip & (1 << square) == 0 {
+                    if seen == compressed {
+                        break;
+                    }
+                    seen += 1;
+                }
+                square += 1;
+            }
+            bits |= 1 << square;
+        }
+
+        bits
+    }
+
+    /// The same position with colours swapped and the board turned upside down.
+    fn mirror(&self, knights: &Knights) -> Knights {
+        Knights {
+            white: self.flip(knights.black),
+            black: self.flip(knights.white),
+            white_to_move: !knights.white_to_move,
+        }
+    }
+
+    fn flip(&self, bits: u64) -> u64 {
+        let width = u32::from(self.width);
+        let last = u32::from(self.height) - 1;
+        let mut flipped = 0;
+        let mut rest = bits;
+
+        while rest != 0 {
+            let square = rest.trailing_zeros();
+            flipped |= 1 << (square % width + (last - square / width) * width);
+            rest &= rest - 1;
+        }
+
+        flipped
+    }
+
+    /// Positions after every move of the side to move.
+    fn children(&self, knights: &Knights, out: &mut Vec<Knights>) {
+        out.clear();
+        let (own, other) = if knights.white_to_move {
+            (knights.white, knights.black)
+        } else {
+            (knights.black, knights.white)
+        };
+
+        let mut pieces = own;
+        while pieces != 0 {
+            let from = pieces.trailing_zeros() as usize;
+            let mut targets = self.attacks[from] & !own;
+
+            while targets != 0 {
+                let to = 1u64 << targets.trailing_zeros();
+                let (own, other) = ((own & !(1 << from)) | to, other & !to);
+                out.push(if knights.white_to_move {
+                    Knights {
+                        white: own,
+                        black: other,
+                        white_to_move: false,
+                    }
+                } else {
+                    Knights {
+                        white: other,
+                        black: own,
+                        white_to_move: true,
+                    }
+                });
+                targets &= targets - 1;
+            }
+            pieces &= pieces - 1;
+        }
+    }
+
+    /// Positions with the same material where the other side made a move
+    /// leading to `knights`.
+    fn parents(&self, knights: &Knights, out: &mut Vec<Knights>) {
+        out.clear();
+        let moved = if knights.white_to_move {
+            knights.black
+        } else {
+            knights.white
+        };
+        let empty = !(knights.white | knights.black);
+
+        let mut pieces = moved;
+        while pieces != 0 {
+            let to = pieces.trailing_zeros() as usize;
+            let mut sources = self.attacks[to] & empty;
+
+            while sources != 0 {
+                let from = 1u64 << sources.trailing_zeros();
+                let moved = (moved & !(1 << to)) | from;
+                out.push(if knights.white_to_move {
+                    Knights {
+                        white: knights.white,
+                        black: moved,
+                        white_to_move: false,
+                    }
+                } else {
+                    Knights {
+                        white: moved,
+                        black: knights.black,
+                        white_to_move: true,
+                    }
+                });
+                sources &= sources - 1;
+            }
+            pieces &= pieces - 1;
+        }
+    }
+
+    /// Value of a position reached by a capture, from an earlier table.
+    /// `None` if the capture ended the game.
+    fn captured(&self, knights: &Knights) -> Option<Value> {
+        let (white, black) = knights.material();
+        if white < self.min_pieces || black < self.min_pieces {
+            return None;
+        }
+
+        Some(
+            self.probe(knights)
+                .expect("smaller tables are solved first"),
+        )
+    }
+
+    fn solve(&self, material: Material) -> Result<Vec<u8>, String> {
+        let len = self.len(material);
+        let mut values = vec![0u8; len as usize];
+        let mut children = vec![];
+        let mut parents = vec![];
+        let mut longest = 0;
+
+        // Positions decided right away: no moves, or decided by captures.
+        for index in 0..len {
+            let knights = self.unindex(material, index);
+            self.children(&knights, &mut children);
+
+            let mut quiet = false;
+            let mut fastest_win: Option<u8> = None;
+            let mut slowest_loss = Some(0);
+
+            for child in &children {
+                if child.material() == material {
+                    quiet = true;
+                    continue;
+                }
+
+                match self.captured(child) {
+                    None => fastest_win = Some(0),
+                    Some(Value::Loss(distance)) => {
+                        fastest_win = Some(fastest_win.map_or(distance, |d| d.min(distance)))
+                    }
+                    Some(Value::Win(distance)) => {
+                        slowest_loss = slowest_loss.map(|d: u8| d.max(distance))
+                    }
+                    Some(Value::Draw) => slowest_loss = None,
+                }
+            }
+
+            let value = match (children.is_empty(), fastest_win, quiet, slowest_loss) {
+                (true, _, _, _) => Value::Loss(0),
+                (_, Some(distance), _, _) => Value::Win(next(distance)?),
+                (_, None, false, Some(distance)) => Value::Loss(next(distance)?),
+                _ => Value::Draw,
+            };
+            longest = longest.max(value.encode());
+            values[index as usize] = value.encode();
+        }
+
+        // Positions decided at distance `distance` decide their parents.
+        let mut distance = 0;
+        while distance < longest {
+            for index in 0..len {
+                let value = Value::decode(values[index as usize]);
+                if value != Value::Loss(distance) && value != Value::Win(distance) {
+                    continue;
+                }
+
+                let knights = self.unindex(material, index);
+                self.parents(&knights, &mut parents);
+
+                for parent in &parents {
+                    let parent_index = self.index(parent) as usize;
+
+                    match (value, Value::decode(values[parent_index])) {
+                        // Moving into a lost position wins.
+                        (Value::Loss(_), Value::Draw) => {}
+                        (Value::Loss(_), Value::Win(current)) if current > distance + 1 => {}
+                        (Value::Loss(_), _) => continue,
+                        // Losing once every move leads to a won position.
+                        (Value::Win(_), Value::Draw) => {
+                            if let Some(slowest) = self.slowest_loss(parent, &values, distance) {
+                                let loss = Value::Loss(next(slowest)?);
+                                longest = longest.max(loss.encode());
+                                values[parent_index] = loss.encode();
+                            }
+                            continue;
+                        }
+                        _ => continue,
+                    }
+
+                    let win = Value::Win(next(distance)?);
+                    longest = longest.max(win.encode());
+                    values[parent_index] = win.encode();
+                }
+            }
+            distance += 1;
+        }
+
+        Ok(values)
+    }
+
+    /// Longest distance among the opponent's wins if every move of
+    /// `knights` leads to one, counting only wins of the same table found
+    /// up to `distance` (later ones may still get shorter).
+    fn slowest_loss(&self, knights: &Knights, values: &[u8], distance: u8) -> Option<u8> {
+        let mut children = vec![];
+        self.children(knights, &mut children);
+        let material = knights.material();
+        let mut slowest = 0;
+
+        for child in &children {
+            let value = if child.material() == material {
+                Value::decode(values[self.index(child) as usize])
+            } else {
+                self.captured(child)?
+            };
+
+            match value {
+                Value::Win(win) if win <= distance || child.material() != material => {
+                    slowest = slowest.max(win)
+                }
+                _ => return None,
+            }
+        }
+
+        Some(slowest)
+    }
+}
+
+impl fmt::Debug for Tablebase {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let materials: Vec<String> = self
+            .materials()
+            .map(|(white, black)| format!("{}v{}", white, black))
+            .collect();
+
+        write!(
+            f,
+            "Tablebase {}x{}, min-pieces {}: {}",
+            self.width,
+            self.height,
+            self.min_pieces,
+            materials.join(", ")
+        )
+    }
+}
+
+/// Material with White having at most as many knights as Black.
+fn canonical((white, black): Material) -> Material {
+    (white.min(black), white.max(black))
+}
+
+/// Distance one ply longer, if it still fits.
+fn next(distance: u8) -> Result<u8, String> {
+    if distance >= MAX_DISTANCE {
+        return Err(String::from("Distance to the end doesn't fit in a byte"));
+    }
+
+    Ok(distance + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn knights(white: &[u32], black: &[u32], white_to_move: bool) -> Knights {
+        Knights {
+            white: white.iter().fold(0, |bits, square| bits | 1 << square),
+            black: black.iter().fold(0, |bits, square| bits | 1 << square),
+            white_to_move,
+        }
+    }
+
+    /// Positions after every knight move of the side to move, found square
+    /// by square instead of with the tablebase's attack masks.
+    fn moves(width: i32, height: i32, position: &Knights) -> Vec<Knights> {
+        let (own, other) = if position.white_to_move {
+            (position.white, position.black)
+        } else {
+            (position.black, position.white)
+        };
+        let mut children = vec![];
+
+        for from in (0..width * height).filter(|square| own & 1 << square != 0) {
+            let (x, y) = (from % width, from / width);
+            for (dx, dy) in &[
+                (1, 2),
+                (2, 1),
+                (2, -1),
+                (1, -2),
+                (-1, -2),
+                (-2, -1),
+                (-2, 1),
+                (-1, 2),
+            ] {
+                let (tx, ty) = (x + dx, y + dy);
+                if tx < 0
+                    || ty < 0
+                    || tx >= width
+                    || ty >= height
+                    || own & 1 << (tx + ty * width) != 0
+                {
+                    continue;
+                }
+
+                let to = 1u64 << (tx + ty * width);
+                let (own, other) = (own & !(1 << from) | to, other & !to);
+                children.push(Knights {
+                    white: if position.white_to_move { own } else { other },
+                    black: if position.white_to_move { other } else { own },
+                    white_to_move: !position.white_to_move,
+                });
+            }
+        }
+
+        children
+    }
+
+    /// Values of every position with 1 to `most` knights a side, by one-ply
+    /// minimax repeated until no value changes.
+    fn brute_force(width: i32, height: i32, most: u32) -> HashMap<(u64, u64, bool), Value> {
+        let squares = width * height;
+        let sets: Vec<u64> = (1u64..1 << squares)
+            .filter(|bits| bits.count_ones() <= most)
+            .collect();
+        let mut positions = vec![];
+        for &white in &sets {
+            for &black in sets.iter().filter(|&&black| black & white == 0) {
+                positions.push(Knights {
+                    white,
+                    black,
+                    white_to_move: true,
+                });
+                positions.push(Knights {
+                    white,
+                    black,
+                    white_to_move: false,
+                });
+            }
+        }
+
+        let key = |position: &Knights| (position.white, position.black, position.white_to_move);
+        let mut values: HashMap<_, _> = positions.iter().map(|p| (key(p), Value::Draw)).collect();
+
+        loop {
+            let mut changed = false;
+
+            for position in &positions {
+                let results: Vec<Value> = moves(width, height, position)
+                    .iter()
+                    .map(|child| {
+                        if child.white == 0 || child.black == 0 {
+                            return Value::Win(1);
+                        }
+                        match values[&key(child)] {
+                            Value::Win(distance) => Value::Loss(distance + 1),
+                            Value::Loss(distance) => Value::Win(distance + 1),
+                            Value::Draw => Value::Draw,
+                        }
+                    })
+                    .collect();
+
+                let fastest_win = results
+                    .iter()
+                    .filter_map(|value| match value {
+                        Value::Win(distance) => Some(*distance),
+                        _ => None,
+                    })
+                    .min();
+                let slowest_loss = results
+                    .iter()
+                    .map(|value| match value {
+                        Value::Loss(distance) => Some(*distance),
+                        _ => None,
+                    })
+                    .try_fold(0, |slowest, distance| distance.map(|d| d.max(slowest)));
+                let value = match (fastest_win, slowest_loss) {
+                    (Some(distance), _) => Value::Win(distance),
+                    (None, Some(distance)) => Value::Loss(distance),
+                    (None, None) => Value::Draw,
+                };
+
+                if values[&key(position)] != value {
+                    values.insert(key(position), value);
+                    changed = true;
+                }
+            }
+
+            if !changed {
+                return values;
+            }
+        }
+    }
+
+    #[test]
+    fn index_round_trip() {
+        let tablebase = Tablebase::new(5, 3, 1).unwrap();
+
+        for &material in &[(1, 1), (1, 2), (2, 3), (3, 2)] {
+            for index in 0..tablebase.len(material) {
+                let position = tablebase.unindex(material, index);
+                assert_eq!(position.material(), material);
+                assert_eq!(tablebase.index(&position), index);
+
+                let mirrored = tablebase.mirror(&position);
+                assert_eq!(mirrored.material(), (material.1, material.0));
+                assert_eq!(tablebase.mirror(&mirrored), position);
+                assert_eq!(
+                    tablebase.unindex(mirrored.material(), tablebase.index(&mirrored)),
+                    mirrored
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn values_match_brute_force() {
+        let tablebase = Tablebase::generate(4, 4, 1, (2, 2), |_, _| {}).unwrap();
+        let values = brute_force(4, 4, 2);
+
+        for (&(white, black, white_to_move), &value) in &values {
+            let position = Knights {
+                white,
+                black,
+                white_to_move,
+            };
+            assert_eq!(tablebase.probe(&position), Some(value), "{:?}", position);
+        }
+    }
+
+    #[test]
+    fn probe() {
+        // 3x3: the centre has no moves, the other squares form a ring.
+        let tablebase = Tablebase::generate(3, 3, 1, (1, 1), |_, _| {}).unwrap();
+
+        // Stuck in the centre.
+        assert_eq!(
+            tablebase.probe(&knights(&[4], &[0], true)),
+            Some(Value::Loss(0))
+        );
+        assert_eq!(
+            tablebase.probe(&knights(&[4], &[0], false)),
+            Some(Value::Win(1))
+        );
+        // Capture of the last knight.
+        assert_eq!(
+            tablebase.probe(&knights(&[0], &[7], true)),
+            Some(Value::Win(1))
+        );
+        assert_eq!(
+            tablebase.probe(&knights(&[0], &[7], false)),
+            Some(Value::Win(1))
+        );
+        // Two steps apart on the ring, stepping next to the other knight loses.
+        assert_eq!(
+            tablebase.probe(&knights(&[0], &[2], true)),
+            Some(Value::Draw)
+        );
+        // No table for more knights.
+        assert_eq!(tablebase.probe(&knights(&[0], &[2, 6], true)), None);
+    }
+}
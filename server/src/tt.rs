@@ -0,0 +1,208 @@
+//! Transposition table: results of searched positions by Zobrist hash,
+//! so positions reached by different move orders are searched once.
+//!
+//! The table is shared by all search threads without locking: every slot is
+//! two atomic words, the entry packed into one and xor-ed with the key in the
+//! other. An entry torn by a concurrent write fails the key check on probe.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::types::Position;
+
+/// How the stored value relates to the real one.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Bound {
+    Exact,
+    /// The search failed high: the real value is at least this.
+    Lower,
+    /// The search failed low: the real value is at most this.
+    Upper,
+}
+
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub key: u64,
+    /// Remaining depth the position was searched to.
+    pub depth: u8,
+    pub value: i32,
+    pub bound: Bound,
+    /// Squares of the move that was best or caused the cut-off.
+    pub best: Option<(Position, Position)>,
+}
+
+impl Entry {
+    /// Packs everything but the key: value in bits 0-31, depth in 32-39,
+    /// bound in 40-41, move flag in 42 and move squares (5 bits per
+    /// coordinate) in 43-62.
+    fn pack(&self) -> u64 {
+        let bound = match self.bound {
+            Bound::Exact => 0,
+            Bound::Lower => 1,
+            Bound::Upper => 2,
+        };
+        let mut data = u64::from(self.value as u32) | u64::from(self.depth) << 32 | bound << 40;
+
+        if let Some((from, to)) = &self.best {
+            let squares = [from.x, from.y, to.x, to.y]
+                .iter()
+                .enumerate()
+                .fold(0, |squares, (i, &c)| squares | u64::from(c) << (5 * i));
+            data |= 1 << 42 | squares << 43;
+        }
+
+        data
+    }
+
+    fn unpack(key: u64, data: u64) -> Entry {
+        let coordinate = |i: u64| ((data >> (43 + 5 * i)) & 0x1f) as u8;
+        let best = if data & 1 << 42 != 0 {
+            Some((
+                Position {
+                    x: coordinate(0),
+                    y: coordinate(1),
+                },
+                Position {
+                    x: coordinate(2),
+                    y: coordinate(3),
+                },
+            ))
+        } else {
+            None
+        };
+
+        Entry {
+            key,
+            depth: (data >> 32) as u8,
+            value: data as u32 as i32,
+            bound: match (data >> 40) & 0b11 {
+                0 => Bound::Exact,
+                1 => Bound::Lower,
+                _ => Bound::Upper,
+            },
+            best,
+        }
+    }
+}
+
+struct Slot {
+    /// Key xor data.
+    check: AtomicU64,
+    data: AtomicU64,
+}
+
+/// Fixed size table, one entry per slot, deeper searches are kept.
+pub struct TranspositionTable {
+    slots: Vec<Slot>,
+}
+
+impl TranspositionTable {
+    /// Table with 2^`bits` slots.
+    pub fn new(bits: u32) -> TranspositionTable {
+        TranspositionTable {
+            slots: (0..1 << bits)
+                .map(|_| Slot {
+                    check: AtomicU64::new(0),
+                    data: AtomicU64::new(0),
+                })
+                .collect(),
+        }
+    }
+
+    fn slot(&self, key: u64) -> &Slot {
+        &self.slots[(key as usize) & (self.slots.len() - 1)]
+    }
+
+    pub fn probe(&self, key: u64) -> Option<Entry> {
+        let slot = self.slot(key);
+        let data = slot.data.load(Ordering::Relaxed);
+        let check = slot.check.load(Ordering::Relaxed);
+
+        if check ^ data == key && data != 0 {
+            Some(Entry::unpack(key, data))
+        } else {
+            None
+        }
+    }
+
+    /// Stores the entry unless the slot holds a deeper search of the same position.
+    pub fn store(&self, entry: Entry) {
+        if let Some(old) = self.probe(entry.key) {
+            if old.depth > entry.depth {
+                return;
+            }
+        }
+
+        let slot = self.slot(entry.key);
+        let data = entry.pack();
+        slot.data.store(data, Ordering::Relaxed);
+        slot.check.store(entry.key ^ data, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: u64 = 0x1234_5678_9abc_def0;
+
+    fn entry(value: i32, depth: u8, bound: Bound, best: Option<(u8, u8, u8, u8)>) -> Entry {
+        Entry {
+            key: KEY,
+            depth,
+            value,
+            bound,
+            best: best
+                .map(|(x1, y1, x2, y2)| (Position { x: x1, y: y1 }, Position { x: x2, y: y2 })),
+        }
+    }
+
+    fn assert_round_trip(entry: &Entry) {
+        let unpacked = Entry::unpack(entry.key, entry.pack());
+
+        assert_eq!(unpacked.key, entry.key);
+        assert_eq!(unpacked.depth, entry.depth);
+        assert_eq!(unpacked.value, entry.value);
+        assert_eq!(unpacked.bound, entry.bound);
+        assert_eq!(unpacked.best, entry.best);
+    }
+
+    #[test]
+    fn pack_round_trip() {
+        assert_round_trip(&entry(0, 0, Bound::Exact, None));
+        assert_round_trip(&entry(250, 7, Bound::Lower, Some((0, 0, 1, 2))));
+        assert_round_trip(&entry(-1, 3, Bound::Upper, Some((25, 25, 24, 23))));
+        assert_round_trip(&entry(-100_000, u8::MAX, Bound::Lower, None));
+        assert_round_trip(&entry(i32::MIN, 1, Bound::Upper, Some((31, 0, 0, 31))));
+        assert_round_trip(&entry(i32::MAX, 2, Bound::Exact, None));
+    }
+
+    #[test]
+    fn probe_checks_the_key() {
+        let table = TranspositionTable::new(4);
+        let stored = entry(-42, 5, Bound::Exact, Some((1, 2, 3, 4)));
+        table.store(stored.clone());
+
+        let found = table.probe(KEY).expect("stored entry");
+        assert_eq!(found.value, -42);
+        assert_eq!(found.best, stored.best);
+        // Another position in the same slot.
+        assert!(table.probe(KEY ^ 1 << 40).is_none());
+
+        // A write torn between the two words.
+        let other = entry(7, 9, Bound::Lower, None);
+        let slot = table.slot(KEY);
+        slot.data.store(other.pack(), Ordering::Relaxed);
+        assert!(table.probe(KEY).is_none());
+    }
+
+    #[test]
+    fn deeper_searches_are_kept() {
+        let table = TranspositionTable::new(4);
+        table.store(entry(10, 6, Bound::Exact, None));
+        table.store(entry(20, 2, Bound::Exact, None));
+        assert_eq!(table.probe(KEY).unwrap().value, 10);
+
+        table.store(entry(30, 6, Bound::Lower, None));
+        assert_eq!(table.probe(KEY).unwrap().value, 30);
+    }
+}
@@ -1,16 +1,99 @@
 use serde::{Deserialize, Serialize};
 
+/// Kind of piece, defined by leaps it moves by (see `Move::valid`).
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub struct PieceKind {
+    pub name: String,
+    /// Letter shown on the board.
+    pub symbol: char,
+    pub leaps: Vec<(u8, u8)>,
+}
+
+impl PieceKind {
+    pub fn leaper(name: &str, symbol: char, leaps: &[(u8, u8)]) -> PieceKind {
+        PieceKind {
+            name: name.to_string(),
+            symbol,
+            leaps: leaps.to_vec(),
+        }
+    }
+
+    pub fn knight() -> PieceKind {
+        PieceKind::leaper("knight", 'N', &[(1, 2)])
+    }
+
+    pub fn camel() -> PieceKind {
+        PieceKind::leaper("camel", 'C', &[(1, 3)])
+    }
+
+    pub fn zebra() -> PieceKind {
+        PieceKind::leaper("zebra", 'Z', &[(2, 3)])
+    }
+
+    pub fn wazir() -> PieceKind {
+        PieceKind::leaper("wazir", 'W', &[(0, 1)])
+    }
+
+    pub fn ferz() -> PieceKind {
+        PieceKind::leaper("ferz", 'F', &[(1, 1)])
+    }
+}
+
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Piece {
     Empty,
-    White,
-    Black,
+    /// Square no piece can land on.
+    Blocked,
+    /// Square out of player's sight in fog games.
+    Hidden,
+    /// Piece of `kind`-th kind of the game.
+    Leaper {
+        kind: u8,
+        owner: Player,
+    },
+}
+
+impl Piece {
+    /// Player owning the piece, if any.
+    pub fn owner(&self) -> Option<Player> {
+        match self {
+            Piece::Empty | Piece::Blocked | Piece::Hidden => None,
+            Piece::Leaper { owner, .. } => Some(owner.clone()),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
 pub enum Player {
     White,
     Black,
+    Red,
+    Blue,
+}
+
+/// Seats of two-player games, in turn order.
+pub const PLAYERS: [Player; 2] = [Player::White, Player::Black];
+
+impl Player {
+    /// Position of the player in `Player` declaration order.
+    pub fn index(&self) -> usize {
+        match self {
+            Player::White => 0,
+            Player::Black => 1,
+            Player::Red => 2,
+            Player::Blue => 3,
+        }
+    }
+
+    /// Player sitting across the board.
+    pub fn opponent(&self) -> Player {
+        match self {
+            Player::White => Player::Black,
+            Player::Black => Player::White,
+            Player::Red => Player::Blue,
+            Player::Blue => Player::Red,
+        }
+    }
 }
 
 #[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
@@ -19,10 +102,15 @@ pub struct Position {
     pub y: u8,
 }
 
+/// Smallest supported board side.
+pub const MIN_BOARD_SIZE: u8 = 5;
+/// Largest supported board side (limited by column letters a..z).
+pub const MAX_BOARD_SIZE: u8 = 26;
+
 impl Position {
-    fn valid(&self) -> bool {
-        let valid_x = self.x <= 7;
-        let valid_y = self.y <= 7;
+    pub fn valid(&self, width: u8, height: u8) -> bool {
+        let valid_x = self.x < width;
+        let valid_y = self.y < height;
 
         valid_x && valid_y
     }
@@ -35,24 +123,121 @@ pub struct Move {
     pub to: Position,
 }
 
-impl Move {
-    pub fn valid(&self) -> bool {
-        let valid_from = self.from.valid();
-        let valid_to = self.to.valid();
+/// How board edges are connected.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Topology {
+    /// Moves can't leave the board.
+    Bounded,
+    /// Moves leaving the board continue from the opposite edge.
+    Toroidal,
+}
 
-        let diff_x = i32::from(self.to.x) - i32::from(self.from.x);
-        let diff_y = i32::from(self.to.y) - i32::from(self.from.y);
+impl Topology {
+    /// Square reached from `from` by offset (dx, dy), if it is on the board.
+    pub fn shift(
+        &self,
+        from: &Position,
+        dx: i32,
+        dy: i32,
+        width: u8,
+        height: u8,
+    ) -> Option<Position> {
+        let (width, height) = (i32::from(width), i32::from(height));
+        let mut x = i32::from(from.x) + dx;
+        let mut y = i32::from(from.y) + dy;
+
+        if *self == Topology::Toroidal {
+            x = x.rem_euclid(width);
+            y = y.rem_euclid(height);
+        }
+
+        if (0..width).contains(&x) && (0..height).contains(&y) {
+            Some(Position {
+                x: x as u8,
+                y: y as u8,
+            })
+        } else {
+            None
+        }
+    }
+}
 
-        let mut valid_move = true;
+/// Offsets of `leaps` in all directions.
+/// Leap (a, b) moves by a squares along one axis and b along the other.
+pub fn leap_offsets(leaps: &[(u8, u8)]) -> Vec<(i32, i32)> {
+    let mut offsets = vec![];
 
-        // TODO: write simpler check for horse move
-        valid_move &= (diff_x.abs()).min(diff_y.abs()) == 1;
-        valid_move &= (diff_x.abs()).max(diff_y.abs()) == 2;
+    for &(a, b) in leaps {
+        for &(dx, dy) in &[(a, b), (b, a)] {
+            for &(sx, sy) in &[(1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                let offset = (sx * i32::from(dx), sy * i32::from(dy));
 
-        valid_from && valid_to && valid_move
+                if !offsets.contains(&offset) {
+                    offsets.push(offset);
+                }
+            }
+        }
+    }
+
+    offsets
+}
+
+impl Move {
+    /// Checks that move stays on `width` x `height` board and is one of the `leaps`.
+    pub fn valid(&self, width: u8, height: u8, topology: &Topology, leaps: &[(u8, u8)]) -> bool {
+        let valid_from = self.from.valid(width, height);
+        let valid_to = self.to.valid(width, height);
+
+        let valid_move = leap_offsets(leaps).into_iter().any(|(dx, dy)| {
+            topology.shift(&self.from, dx, dy, width, height).as_ref() == Some(&self.to)
+        });
+
+        valid_from && valid_to && valid_move && self.from != self.to
     }
 }
 
 pub type History = Vec<Move>;
 
-pub type Board = [[Piece; 8]; 8];
+pub type Board = Vec<Vec<Piece>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shift(topology: Topology, (x, y): (u8, u8), dx: i32, dy: i32) -> Option<(u8, u8)> {
+        topology
+            .shift(&Position { x, y }, dx, dy, 8, 6)
+            .map(|to| (to.x, to.y))
+    }
+
+    #[test]
+    fn bounded_shift_stays_on_the_board() {
+        assert_eq!(shift(Topology::Bounded, (3, 2), 1, 2), Some((4, 4)));
+        assert_eq!(shift(Topology::Bounded, (3, 2), -2, -1), Some((1, 1)));
+        assert_eq!(shift(Topology::Bounded, (0, 0), -1, 2), None);
+        assert_eq!(shift(Topology::Bounded, (7, 5), 1, -2), None);
+        assert_eq!(shift(Topology::Bounded, (6, 4), 1, 2), None);
+    }
+
+    #[test]
+    fn toroidal_shift_wraps_around() {
+        assert_eq!(shift(Topology::Toroidal, (7, 5), 1, 2), Some((0, 1)));
+        assert_eq!(shift(Topology::Toroidal, (6, 4), 2, 1), Some((0, 5)));
+        assert_eq!(shift(Topology::Toroidal, (3, 2), 1, 2), Some((4, 4)));
+    }
+
+    #[test]
+    fn toroidal_shift_wraps_negative_offsets() {
+        assert_eq!(shift(Topology::Toroidal, (0, 0), -1, -2), Some((7, 4)));
+        assert_eq!(shift(Topology::Toroidal, (1, 0), -2, 1), Some((7, 1)));
+        assert_eq!(shift(Topology::Toroidal, (0, 1), -8, -6), Some((0, 1)));
+    }
+
+    #[test]
+    fn toroidal_shift_wraps_offsets_longer_than_a_side() {
+        assert_eq!(shift(Topology::Toroidal, (3, 2), 8, 6), Some((3, 2)));
+        assert_eq!(shift(Topology::Toroidal, (3, 2), 17, 13), Some((4, 3)));
+        assert_eq!(shift(Topology::Toroidal, (3, 2), -17, -13), Some((2, 1)));
+        assert_eq!(shift(Topology::Bounded, (3, 2), 17, 0), None);
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Condition deciding the game winner.
+/// Independently of it, player who can't move loses.
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+pub enum Victory {
+    /// Opponent has fewer than N pieces.
+    MinPieces(u8),
+    /// Opponent has no pieces left.
+    CaptureAll,
+    /// N own pieces stand on the opponent's home row.
+    BackRank(u8),
+    /// Own pieces occupy every center square.
+    KingOfTheHill,
+    /// Made K captures.
+    Captures(u8),
+    /// Only leaving the opponent without moves wins.
+    Immobilize,
+}
+
+pub const VICTORY_HELP: &str =
+    "min-pieces:N, capture-all, back-rank:N, king-of-the-hill, captures:K, immobilize";
+
+impl FromStr for Victory {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Victory, String> {
+        let mut parts = s.splitn(2, ':');
+        let name = parts.next().unwrap_or("");
+        let count = parts.next().map(|count| {
+            count
+                .parse::<u8>()
+                .ok()
+                .filter(|&count| count > 0)
+                .ok_or_else(|| format!("'{}' is not a positive number", count))
+        });
+
+        match (name, count) {
+            ("min-pieces", Some(count)) => Ok(Victory::MinPieces(count?)),
+            ("capture-all", None) => Ok(Victory::CaptureAll),
+            ("back-rank", Some(count)) => Ok(Victory::BackRank(count?)),
+            ("king-of-the-hill", None) => Ok(Victory::KingOfTheHill),
+            ("captures", Some(count)) => Ok(Victory::Captures(count?)),
+            ("immobilize", None) => Ok(Victory::Immobilize),
+            _ => Err(format!(
+                "Unknown victory condition '{}', expected one of: {}",
+                s, VICTORY_HELP
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Victory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Victory::MinPieces(count) => write!(f, "opponent has fewer than {} pieces", count),
+            Victory::CaptureAll => write!(f, "capture all pieces"),
+            Victory::BackRank(count) => write!(f, "{} pieces on opponent's home row", count),
+            Victory::KingOfTheHill => write!(f, "occupy the center"),
+            Victory::Captures(count) => write!(f, "first to {} captures", count),
+            Victory::Immobilize => write!(f, "leave opponent without moves"),
+        }
+    }
+}
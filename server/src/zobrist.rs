@@ -0,0 +1,82 @@
+//! Zobrist keys for position hashing.
+//!
+//! Keys are computed from their index with splitmix64 instead of being drawn
+//! from a random table, so hashes are the same in every run and every program.
+
+/// Number of distinct keys per square: piece kinds times owners, plus
+/// blocked and hidden squares.
+const SQUARE_KEYS: u64 = 256 * 4 + 2;
+/// Squares on the largest board.
+const SQUARES: u64 = 26 * 26;
+
+/// Groups of keys, each starts after the previous one.
+const SQUARES_BASE: u64 = 0;
+const TO_MOVE_BASE: u64 = SQUARES_BASE + SQUARES * SQUARE_KEYS;
+const READY_BASE: u64 = TO_MOVE_BASE + 4;
+const ELIMINATED_BASE: u64 = READY_BASE + 4;
+const CAPTURES_BASE: u64 = ELIMINATED_BASE + 4;
+const EXTRA_MOVES_BASE: u64 = CAPTURES_BASE + 4 * 256;
+const PENDING_BASE: u64 = EXTRA_MOVES_BASE + 4 * 256;
+
+/// splitmix64 output for `index`.
+pub fn key(index: u64) -> u64 {
+    let mut z = index.wrapping_add(1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// Key of a leaper of `kind` owned by player with `owner` index on square (x, y).
+pub fn piece(x: u8, y: u8, kind: u8, owner: usize) -> u64 {
+    square(x, y, u64::from(kind) * 4 + owner as u64)
+}
+
+/// Key of a blocked square.
+pub fn blocked(x: u8, y: u8) -> u64 {
+    square(x, y, SQUARE_KEYS - 2)
+}
+
+/// Key of a square hidden by fog.
+pub fn hidden(x: u8, y: u8) -> u64 {
+    square(x, y, SQUARE_KEYS - 1)
+}
+
+fn square_index(x: u8, y: u8) -> u64 {
+    u64::from(y) * 26 + u64::from(x)
+}
+
+fn square(x: u8, y: u8, content: u64) -> u64 {
+    key(SQUARES_BASE + square_index(x, y) * SQUARE_KEYS + content)
+}
+
+/// Key of player with index `player` being the one to move.
+pub fn to_move(player: usize) -> u64 {
+    key(TO_MOVE_BASE + player as u64)
+}
+
+/// Key of player with index `player` having submitted a simultaneous move.
+pub fn ready(player: usize) -> u64 {
+    key(READY_BASE + player as u64)
+}
+
+/// Key of player with index `player` being out of the game.
+pub fn eliminated(player: usize) -> u64 {
+    key(ELIMINATED_BASE + player as u64)
+}
+
+/// Key of player with index `player` having made `count` captures.
+pub fn captures(player: usize, count: u8) -> u64 {
+    key(CAPTURES_BASE + player as u64 * 256 + u64::from(count))
+}
+
+/// Key of player with index `player` having `count` extra moves left.
+pub fn extra_moves(player: usize, count: u8) -> u64 {
+    key(EXTRA_MOVES_BASE + player as u64 * 256 + u64::from(count))
+}
+
+/// Key of a simultaneous move submitted but not applied yet.
+pub fn pending(from: (u8, u8), to: (u8, u8)) -> u64 {
+    let from = square_index(from.0, from.1);
+    let to = square_index(to.0, to.1);
+    key(PENDING_BASE + from * SQUARES + to)
+}